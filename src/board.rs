@@ -1,549 +1,348 @@
-use bevy::prelude::*;
-use std::{collections::HashMap, fmt};
-
-use crate::{assets::BoardAssets, types::Board};
-
-#[derive(Debug, Clone, PartialEq, Eq)]
-pub enum File {
-    A,
-    B,
-    C,
-    D,
-    E,
-    F,
-    G,
-    H,
+use bevy::{prelude::*, render::camera::OrthographicProjection};
+use bevy_egui::EguiSettings;
+
+use crate::{
+    assets::{BoardAssets, TextAssets},
+    camera::ChessCamera,
+    persist::Settings,
+    pieces::{Kind, Piece, SelectedPiece, Side, SourcePiece},
+};
+pub use chessbored::square::{File, Rank, Square};
+
+/// where everything sits in the world, and how the world fits the window right now.
+/// world positions don't change when the window does, the camera scales instead
+#[derive(Component, Debug, Clone)]
+pub struct BoardLayout {
+    // world units per square, pieces and squares are drawn this size
+    pub square_size: f32,
+    // the window size the board, palette and panels are laid out for
+    pub reference_size: Vec2,
+    // the column down the right of the reference window kept for side panels
+    pub panel_width: f32,
+    // window pixels per world unit
+    pub scale: f32,
+    // empty pixels left and below when the window's proportions aren't the reference's
+    pub margin: Vec2,
+    // black's side at the bottom
+    pub flipped: bool,
 }
 
-impl fmt::Display for File {
-    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
-        match self {
-            File::A => fmt.write_str("a"),
-            File::B => fmt.write_str("b"),
-            File::C => fmt.write_str("c"),
-            File::D => fmt.write_str("d"),
-            File::E => fmt.write_str("e"),
-            File::F => fmt.write_str("f"),
-            File::G => fmt.write_str("g"),
-            File::H => fmt.write_str("h"),
+impl Default for BoardLayout {
+    fn default() -> Self {
+        BoardLayout {
+            square_size: 50.0,
+            reference_size: Vec2::new(900.0, 700.0),
+            panel_width: 220.0,
+            scale: 1.0,
+            margin: Vec2::ZERO,
+            flipped: false,
         }
     }
 }
 
-#[derive(Debug, Clone, PartialEq, Eq)]
-pub enum Rank {
-    One,
-    Two,
-    Three,
-    Four,
-    Five,
-    Six,
-    Seven,
-    Eight,
-}
+impl BoardLayout {
+    pub fn square_center(&self, square: &Square) -> Vec2 {
+        Vec2::new(
+            self.oriented(square.file.index()) as f32 * self.square_size,
+            self.oriented(square.rank.index()) as f32 * self.square_size,
+        )
+    }
 
-impl fmt::Display for Rank {
-    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
-        match self {
-            Rank::One => fmt.write_str("1"),
-            Rank::Two => fmt.write_str("2"),
-            Rank::Three => fmt.write_str("3"),
-            Rank::Four => fmt.write_str("4"),
-            Rank::Five => fmt.write_str("5"),
-            Rank::Six => fmt.write_str("6"),
-            Rank::Seven => fmt.write_str("7"),
-            Rank::Eight => fmt.write_str("8"),
+    // a file or rank index counted from the bottom left of the screen, or back again
+    fn oriented(&self, index: usize) -> usize {
+        if self.flipped {
+            7 - index
+        } else {
+            index
         }
     }
-}
 
-#[derive(Debug, Clone, PartialEq, Eq)]
-pub struct Square {
-    pub file: File,
-    pub rank: Rank,
+    /// the bottom left corner of a1
+    pub fn board_min(&self) -> Vec2 {
+        Vec2::splat(-self.square_size / 2.0)
+    }
+
+    /// the top right corner of h8
+    pub fn board_max(&self) -> Vec2 {
+        Vec2::splat(7.5 * self.square_size)
+    }
+
+    pub fn board_center(&self) -> Vec2 {
+        (self.board_min() + self.board_max()) / 2.0
+    }
+
+    /// where the camera looks, the board's center nudged over to leave the side panels
+    /// their column
+    pub fn view_center(&self) -> Vec2 {
+        self.board_center() + Vec2::new(self.panel_width / 2.0, 0.0)
+    }
+
+    /// the left edge of the side panel column in the reference window
+    pub fn panel_left(&self) -> f32 {
+        self.reference_size.x - self.panel_width
+    }
+
+    /// the middle of the eval bar down the left of the board, clear of the rank numbers
+    pub fn eval_bar_center(&self) -> Vec2 {
+        Vec2::new(
+            self.board_min().x - 0.8 * self.square_size,
+            self.board_center().y,
+        )
+    }
+
+    /// the eval bar runs the height of the board
+    pub fn eval_bar_size(&self) -> Vec2 {
+        Vec2::new(
+            0.3 * self.square_size,
+            self.board_max().y - self.board_min().y,
+        )
+    }
+
+    /// the square under a world position, if it's on the board
+    pub fn square_at(&self, pos: Vec2) -> Option<Square> {
+        let (min, max) = (self.board_min(), self.board_max());
+        if pos.x < min.x || pos.y < min.y || pos.x > max.x || pos.y > max.y {
+            return None;
+        }
+
+        let index = |n: f32| self.oriented((((n - min.x) / self.square_size) as usize).min(7));
+        Some(Square::new(
+            File::from_index(index(pos.x))?,
+            Rank::from_index(index(pos.y))?,
+        ))
+    }
+
+    /// pieces can be let go anywhere but over the palette or below the board
+    pub fn can_drop(&self, pos: Vec2) -> bool {
+        pos.x <= self.board_max().x && pos.y >= self.board_min().y
+    }
+
+    /// where `side` sits in the palette off the right edge of the board, level with
+    /// the three ranks nearest that side
+    pub fn palette_position(&self, side: &Side) -> Vec2 {
+        let (column, row) = match side.kind() {
+            Kind::Pawn => (0, 0),
+            Kind::Rook => (1, 0),
+            Kind::Knight => (0, 1),
+            Kind::Bishop => (1, 1),
+            Kind::Queen => (0, 2),
+            Kind::King => (1, 2),
+        };
+        let row = match side {
+            Side::White(_) => self.oriented(row),
+            Side::Black(_) => self.oriented(7 - row),
+        };
+        Vec2::new(
+            self.board_max().x + 0.7 * self.square_size + column as f32 * self.square_size,
+            row as f32 * self.square_size,
+        )
+    }
+
+    /// a point in the reference window, in pixels from its bottom left, as
+    /// pixels from the bottom left of the actual window
+    pub fn ui_pixels(&self, x: f32, y: f32) -> Vec2 {
+        self.margin + Vec2::new(x, y) * self.scale
+    }
+
+    /// the same as `ui_pixels` but for egui, which is already scaled
+    pub fn ui_points(&self, x: f32, y: f32) -> Vec2 {
+        self.margin / self.scale + Vec2::new(x, y)
+    }
+
+    fn fit(&mut self, window: Vec2) {
+        self.scale = (window / self.reference_size).min_element();
+        self.margin = (window - self.reference_size * self.scale) / 2.0;
+    }
 }
 
-impl fmt::Display for Square {
-    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
-        write!(fmt, "{}{}", self.file, self.rank)
+/// scale the camera and the panels whenever the window changes size
+pub fn fit_to_window(
+    windows: Res<Windows>,
+    mut layout: ResMut<BoardLayout>,
+    mut egui_settings: ResMut<EguiSettings>,
+    mut camera_query: Query<&mut OrthographicProjection, With<ChessCamera>>,
+) {
+    let window = match windows.get_primary() {
+        Some(window) => Vec2::new(window.width(), window.height()),
+        None => return,
+    };
+    if window.x <= 0.0 || window.y <= 0.0 {
+        // minimised
+        return;
     }
+
+    let mut fitted = layout.clone();
+    fitted.fit(window);
+    if fitted.scale == layout.scale && fitted.margin == layout.margin {
+        return;
+    }
+    *layout = fitted;
+
+    for mut projection in camera_query.iter_mut() {
+        projection.scale = 1.0 / layout.scale;
+    }
+    egui_settings.scale_factor = layout.scale as f64;
 }
 
-impl Square {
-    pub fn new(rank: File, file: Rank) -> Square {
-        Square {
-            file: rank,
-            rank: file,
+/// a square sprite and the square it's drawn for
+#[derive(Component)]
+pub struct BoardSquare(pub Square);
+
+/// turn the board around, everything keeps its real square and moves to where that
+/// square is drawn now
+pub fn flip_board(
+    keys: Res<Input<KeyCode>>,
+    mut layout: ResMut<BoardLayout>,
+    mut square_query: Query<(&BoardSquare, &mut Transform), Without<Piece>>,
+    mut piece_query: Query<(
+        &mut Piece,
+        &mut Transform,
+        Option<&SourcePiece>,
+        Option<&SelectedPiece>,
+    )>,
+) {
+    if !keys.just_pressed(KeyCode::F) {
+        return;
+    }
+
+    let before = layout.clone();
+    layout.flipped = !layout.flipped;
+
+    let moved = |pos: Vec3| match before.square_at(pos.truncate()) {
+        Some(square) => layout.square_center(&square).extend(pos.z),
+        None => pos,
+    };
+
+    for (square, mut transform) in square_query.iter_mut() {
+        let pos = layout.square_center(&square.0);
+        transform.translation = pos.extend(transform.translation.z);
+    }
+
+    for (mut piece, mut transform, source, selected) in piece_query.iter_mut() {
+        if source.is_some() {
+            let pos = layout.palette_position(&piece.def);
+            transform.translation = pos.extend(transform.translation.z);
+            continue;
         }
+        if selected.is_none() {
+            // the piece in hand stays under the cursor
+            transform.translation = moved(transform.translation);
+        }
+        piece.selected_translation = piece.selected_translation.map(moved);
     }
 }
 
-pub fn get_square(x: f32, y: f32) -> Option<Square> {
-    // on the board
-    if y >= -25.0 && x >= -25.0 && y <= 365.0 && x <= 365.0 {
-        let rank = if x <= 25.0 {
-            File::A
-        } else if x <= 75.0 {
-            File::B
-        } else if x <= 125.0 {
-            File::C
-        } else if x <= 175.0 {
-            File::D
-        } else if x <= 225.0 {
-            File::E
-        } else if x <= 275.0 {
-            File::F
-        } else if x <= 325.0 {
-            File::G
+pub fn setup_board(mut commands: Commands, assets: ResMut<BoardAssets>, layout: Res<BoardLayout>) {
+    let sz = -0.01;
+
+    for square in Square::all() {
+        let texture = if square.is_light() {
+            assets.light_square_handle.clone()
         } else {
-            File::H
+            assets.dark_square_handle.clone()
         };
+        let pos = layout.square_center(&square);
+        commands
+            .spawn_bundle(SpriteBundle {
+                sprite: Sprite {
+                    custom_size: Some(Vec2::splat(layout.square_size)),
+                    ..default()
+                },
+                texture,
+                transform: Transform::from_xyz(pos.x, pos.y, sz),
+                ..default()
+            })
+            .insert(BoardSquare(square));
+    }
+}
 
-        let file = if y <= 25.0 {
-            Rank::One
-        } else if y <= 75.0 {
-            Rank::Two
-        } else if y <= 125.0 {
-            Rank::Three
-        } else if y <= 175.0 {
-            Rank::Four
-        } else if y <= 225.0 {
-            Rank::Five
-        } else if y <= 275.0 {
-            Rank::Six
-        } else if y <= 325.0 {
-            Rank::Seven
-        } else {
-            Rank::Eight
+/// a file letter or rank number drawn along the edge of the board
+#[derive(Component)]
+pub enum CoordinateLabel {
+    File(File),
+    Rank(Rank),
+}
+
+impl CoordinateLabel {
+    fn position(&self, layout: &BoardLayout) -> Vec2 {
+        // just outside the bottom and left edges, whichever squares are there
+        let gap = 0.3 * layout.square_size;
+        match self {
+            Self::File(file) => Vec2::new(
+                layout.square_center(&Square::new(*file, Rank::One)).x,
+                layout.board_min().y - gap,
+            ),
+            Self::Rank(rank) => Vec2::new(
+                layout.board_min().x - gap,
+                layout.square_center(&Square::new(File::A, *rank)).y,
+            ),
+        }
+    }
+}
+
+pub fn setup_coordinates(
+    mut commands: Commands,
+    text_assets: Res<TextAssets>,
+    layout: Res<BoardLayout>,
+    settings: Res<Settings>,
+) {
+    let labels = File::ALL
+        .into_iter()
+        .map(CoordinateLabel::File)
+        .chain(Rank::ALL.into_iter().map(CoordinateLabel::Rank));
+
+    for label in labels {
+        let value = match &label {
+            CoordinateLabel::File(file) => file.to_string(),
+            CoordinateLabel::Rank(rank) => rank.to_string(),
         };
-        Some(Square::new(rank, file))
-    } else {
-        None
+        let pos = label.position(&layout);
+        commands
+            .spawn_bundle(Text2dBundle {
+                text: Text {
+                    sections: vec![TextSection {
+                        value,
+                        style: TextStyle {
+                            font: text_assets.regular_font_handle.clone(),
+                            font_size: 14.0,
+                            color: Color::rgb(0.15, 0.15, 0.15),
+                        },
+                    }],
+                    alignment: TextAlignment {
+                        vertical: VerticalAlign::Center,
+                        horizontal: HorizontalAlign::Center,
+                    },
+                },
+                transform: Transform::from_xyz(pos.x, pos.y, 0.0),
+                visibility: Visibility {
+                    is_visible: settings.show_coordinates,
+                },
+                ..default()
+            })
+            .insert(label);
     }
 }
 
-pub fn board_map() -> Board {
-    let mut board = HashMap::new();
-
-    board.insert("a1", Vec2::new(0.0, 0.0));
-    board.insert("b1", Vec2::new(50.0, 0.0));
-    board.insert("c1", Vec2::new(100.0, 0.0));
-    board.insert("d1", Vec2::new(150.0, 0.0));
-    board.insert("e1", Vec2::new(200.0, 0.0));
-    board.insert("f1", Vec2::new(250.0, 0.0));
-    board.insert("g1", Vec2::new(300.0, 0.0));
-    board.insert("h1", Vec2::new(350.0, 0.0));
-
-    board.insert("a2", Vec2::new(0.0, 50.0));
-    board.insert("b2", Vec2::new(50.0, 50.0));
-    board.insert("c2", Vec2::new(100.0, 50.0));
-    board.insert("d2", Vec2::new(150.0, 50.0));
-    board.insert("e2", Vec2::new(200.0, 50.0));
-    board.insert("f2", Vec2::new(250.0, 50.0));
-    board.insert("g2", Vec2::new(300.0, 50.0));
-    board.insert("h2", Vec2::new(350.0, 50.0));
-
-    board.insert("a3", Vec2::new(0.0, 100.0));
-    board.insert("b3", Vec2::new(50.0, 100.0));
-    board.insert("c3", Vec2::new(100.0, 100.0));
-    board.insert("d3", Vec2::new(150.0, 100.0));
-    board.insert("e3", Vec2::new(200.0, 100.0));
-    board.insert("f3", Vec2::new(250.0, 100.0));
-    board.insert("g3", Vec2::new(300.0, 100.0));
-    board.insert("h3", Vec2::new(350.0, 100.0));
-
-    board.insert("a4", Vec2::new(0.0, 150.0));
-    board.insert("b4", Vec2::new(50.0, 150.0));
-    board.insert("c4", Vec2::new(100.0, 150.0));
-    board.insert("d4", Vec2::new(150.0, 150.0));
-    board.insert("e4", Vec2::new(200.0, 150.0));
-    board.insert("f4", Vec2::new(250.0, 150.0));
-    board.insert("g4", Vec2::new(300.0, 150.0));
-    board.insert("h4", Vec2::new(350.0, 150.0));
-
-    board.insert("a5", Vec2::new(0.0, 200.0));
-    board.insert("b5", Vec2::new(50.0, 200.0));
-    board.insert("c5", Vec2::new(100.0, 200.0));
-    board.insert("d5", Vec2::new(150.0, 200.0));
-    board.insert("e5", Vec2::new(200.0, 200.0));
-    board.insert("f5", Vec2::new(250.0, 200.0));
-    board.insert("g5", Vec2::new(300.0, 200.0));
-    board.insert("h5", Vec2::new(350.0, 200.0));
-
-    board.insert("a6", Vec2::new(0.0, 250.0));
-    board.insert("b6", Vec2::new(50.0, 250.0));
-    board.insert("c6", Vec2::new(100.0, 250.0));
-    board.insert("d6", Vec2::new(150.0, 250.0));
-    board.insert("e6", Vec2::new(200.0, 250.0));
-    board.insert("f6", Vec2::new(250.0, 250.0));
-    board.insert("g6", Vec2::new(300.0, 250.0));
-    board.insert("h6", Vec2::new(350.0, 250.0));
-
-    board.insert("a7", Vec2::new(0.0, 300.0));
-    board.insert("b7", Vec2::new(50.0, 300.0));
-    board.insert("c7", Vec2::new(100.0, 300.0));
-    board.insert("d7", Vec2::new(150.0, 300.0));
-    board.insert("e7", Vec2::new(200.0, 300.0));
-    board.insert("f7", Vec2::new(250.0, 300.0));
-    board.insert("g7", Vec2::new(300.0, 300.0));
-    board.insert("h7", Vec2::new(350.0, 300.0));
-
-    board.insert("a8", Vec2::new(0.0, 350.0));
-    board.insert("b8", Vec2::new(50.0, 350.0));
-    board.insert("c8", Vec2::new(100.0, 350.0));
-    board.insert("d8", Vec2::new(150.0, 350.0));
-    board.insert("e8", Vec2::new(200.0, 350.0));
-    board.insert("f8", Vec2::new(250.0, 350.0));
-    board.insert("g8", Vec2::new(300.0, 350.0));
-    board.insert("h8", Vec2::new(350.0, 350.0));
-
-    // side selection
-    board.insert("wq", Vec2::new(410.0, 100.0));
-    board.insert("wk", Vec2::new(460.0, 100.0));
-    board.insert("wn", Vec2::new(410.0, 50.0));
-    board.insert("wb", Vec2::new(460.0, 50.0));
-    board.insert("wp", Vec2::new(410.0, 0.0));
-    board.insert("wr", Vec2::new(460.0, 0.0));
-
-    board.insert("bq", Vec2::new(410.0, 350.0));
-    board.insert("bk", Vec2::new(460.0, 350.0));
-    board.insert("bn", Vec2::new(410.0, 300.0));
-    board.insert("bb", Vec2::new(460.0, 300.0));
-    board.insert("bp", Vec2::new(410.0, 250.0));
-    board.insert("br", Vec2::new(460.0, 250.0));
-
-    board
+pub fn toggle_coordinates(keys: Res<Input<KeyCode>>, mut settings: ResMut<Settings>) {
+    if keys.just_pressed(KeyCode::L) {
+        settings.show_coordinates = !settings.show_coordinates;
+    }
 }
 
-pub fn setup_board(mut commands: Commands, assets: ResMut<BoardAssets>, board: Res<Board>) {
-    let sz = -0.01;
+/// keep the labels beside the squares they name as the board flips, and
+/// hidden when they're switched off
+pub fn place_coordinates(
+    layout: Res<BoardLayout>,
+    settings: Res<Settings>,
+    mut label_query: Query<(&CoordinateLabel, &mut Transform, &mut Visibility)>,
+) {
+    if !layout.is_changed() && !settings.is_changed() {
+        return;
+    }
 
-    // row 1
-    commands.spawn_bundle(SpriteBundle {
-        texture: assets.dark_square_handle.clone(),
-        transform: Transform::from_xyz(board.get("a1").unwrap().x, board.get("a1").unwrap().y, sz),
-        ..default()
-    });
-    commands.spawn_bundle(SpriteBundle {
-        texture: assets.light_square_handle.clone(),
-        transform: Transform::from_xyz(board.get("b1").unwrap().x, board.get("b1").unwrap().y, sz),
-        ..default()
-    });
-    commands.spawn_bundle(SpriteBundle {
-        texture: assets.dark_square_handle.clone(),
-        transform: Transform::from_xyz(board.get("c1").unwrap().x, board.get("c1").unwrap().y, sz),
-        ..default()
-    });
-    commands.spawn_bundle(SpriteBundle {
-        texture: assets.light_square_handle.clone(),
-        transform: Transform::from_xyz(board.get("d1").unwrap().x, board.get("d1").unwrap().y, sz),
-        ..default()
-    });
-    commands.spawn_bundle(SpriteBundle {
-        texture: assets.dark_square_handle.clone(),
-        transform: Transform::from_xyz(board.get("e1").unwrap().x, board.get("e1").unwrap().y, sz),
-        ..default()
-    });
-    commands.spawn_bundle(SpriteBundle {
-        texture: assets.light_square_handle.clone(),
-        transform: Transform::from_xyz(board.get("f1").unwrap().x, board.get("f1").unwrap().y, sz),
-        ..default()
-    });
-    commands.spawn_bundle(SpriteBundle {
-        texture: assets.dark_square_handle.clone(),
-        transform: Transform::from_xyz(board.get("g1").unwrap().x, board.get("g1").unwrap().y, sz),
-        ..default()
-    });
-    commands.spawn_bundle(SpriteBundle {
-        texture: assets.light_square_handle.clone(),
-        transform: Transform::from_xyz(board.get("h1").unwrap().x, board.get("h1").unwrap().y, sz),
-        ..default()
-    });
-    // row 2
-    commands.spawn_bundle(SpriteBundle {
-        texture: assets.light_square_handle.clone(),
-        transform: Transform::from_xyz(board.get("a2").unwrap().x, board.get("a2").unwrap().y, sz),
-        ..default()
-    });
-    commands.spawn_bundle(SpriteBundle {
-        texture: assets.dark_square_handle.clone(),
-        transform: Transform::from_xyz(board.get("b2").unwrap().x, board.get("b2").unwrap().y, sz),
-        ..default()
-    });
-    commands.spawn_bundle(SpriteBundle {
-        texture: assets.light_square_handle.clone(),
-        transform: Transform::from_xyz(board.get("c2").unwrap().x, board.get("c2").unwrap().y, sz),
-        ..default()
-    });
-    commands.spawn_bundle(SpriteBundle {
-        texture: assets.dark_square_handle.clone(),
-        transform: Transform::from_xyz(board.get("d2").unwrap().x, board.get("d2").unwrap().y, sz),
-        ..default()
-    });
-    commands.spawn_bundle(SpriteBundle {
-        texture: assets.light_square_handle.clone(),
-        transform: Transform::from_xyz(board.get("e2").unwrap().x, board.get("e2").unwrap().y, sz),
-        ..default()
-    });
-    commands.spawn_bundle(SpriteBundle {
-        texture: assets.dark_square_handle.clone(),
-        transform: Transform::from_xyz(board.get("f2").unwrap().x, board.get("f2").unwrap().y, sz),
-        ..default()
-    });
-    commands.spawn_bundle(SpriteBundle {
-        texture: assets.light_square_handle.clone(),
-        transform: Transform::from_xyz(board.get("g2").unwrap().x, board.get("g2").unwrap().y, sz),
-        ..default()
-    });
-    commands.spawn_bundle(SpriteBundle {
-        texture: assets.dark_square_handle.clone(),
-        transform: Transform::from_xyz(board.get("h2").unwrap().x, board.get("h2").unwrap().y, sz),
-        ..default()
-    });
-    // row 3
-    commands.spawn_bundle(SpriteBundle {
-        texture: assets.dark_square_handle.clone(),
-        transform: Transform::from_xyz(board.get("a3").unwrap().x, board.get("a3").unwrap().y, sz),
-        ..default()
-    });
-    commands.spawn_bundle(SpriteBundle {
-        texture: assets.light_square_handle.clone(),
-        transform: Transform::from_xyz(board.get("b3").unwrap().x, board.get("b3").unwrap().y, sz),
-        ..default()
-    });
-    commands.spawn_bundle(SpriteBundle {
-        texture: assets.dark_square_handle.clone(),
-        transform: Transform::from_xyz(board.get("c3").unwrap().x, board.get("c3").unwrap().y, sz),
-        ..default()
-    });
-    commands.spawn_bundle(SpriteBundle {
-        texture: assets.light_square_handle.clone(),
-        transform: Transform::from_xyz(board.get("d3").unwrap().x, board.get("d3").unwrap().y, sz),
-        ..default()
-    });
-    commands.spawn_bundle(SpriteBundle {
-        texture: assets.dark_square_handle.clone(),
-        transform: Transform::from_xyz(board.get("e3").unwrap().x, board.get("e3").unwrap().y, sz),
-        ..default()
-    });
-    commands.spawn_bundle(SpriteBundle {
-        texture: assets.light_square_handle.clone(),
-        transform: Transform::from_xyz(board.get("f3").unwrap().x, board.get("f3").unwrap().y, sz),
-        ..default()
-    });
-    commands.spawn_bundle(SpriteBundle {
-        texture: assets.dark_square_handle.clone(),
-        transform: Transform::from_xyz(board.get("g3").unwrap().x, board.get("g3").unwrap().y, sz),
-        ..default()
-    });
-    commands.spawn_bundle(SpriteBundle {
-        texture: assets.light_square_handle.clone(),
-        transform: Transform::from_xyz(board.get("h3").unwrap().x, board.get("h3").unwrap().y, sz),
-        ..default()
-    });
-    // row 4
-    commands.spawn_bundle(SpriteBundle {
-        texture: assets.light_square_handle.clone(),
-        transform: Transform::from_xyz(board.get("a4").unwrap().x, board.get("a4").unwrap().y, sz),
-        ..default()
-    });
-    commands.spawn_bundle(SpriteBundle {
-        texture: assets.dark_square_handle.clone(),
-        transform: Transform::from_xyz(board.get("b4").unwrap().x, board.get("b4").unwrap().y, sz),
-        ..default()
-    });
-    commands.spawn_bundle(SpriteBundle {
-        texture: assets.light_square_handle.clone(),
-        transform: Transform::from_xyz(board.get("c4").unwrap().x, board.get("c4").unwrap().y, sz),
-        ..default()
-    });
-    commands.spawn_bundle(SpriteBundle {
-        texture: assets.dark_square_handle.clone(),
-        transform: Transform::from_xyz(board.get("d4").unwrap().x, board.get("d4").unwrap().y, sz),
-        ..default()
-    });
-    commands.spawn_bundle(SpriteBundle {
-        texture: assets.light_square_handle.clone(),
-        transform: Transform::from_xyz(board.get("e4").unwrap().x, board.get("e4").unwrap().y, sz),
-        ..default()
-    });
-    commands.spawn_bundle(SpriteBundle {
-        texture: assets.dark_square_handle.clone(),
-        transform: Transform::from_xyz(board.get("f4").unwrap().x, board.get("f4").unwrap().y, sz),
-        ..default()
-    });
-    commands.spawn_bundle(SpriteBundle {
-        texture: assets.light_square_handle.clone(),
-        transform: Transform::from_xyz(board.get("g4").unwrap().x, board.get("g4").unwrap().y, sz),
-        ..default()
-    });
-    commands.spawn_bundle(SpriteBundle {
-        texture: assets.dark_square_handle.clone(),
-        transform: Transform::from_xyz(board.get("h4").unwrap().x, board.get("h4").unwrap().y, sz),
-        ..default()
-    });
-    // row 5
-    commands.spawn_bundle(SpriteBundle {
-        texture: assets.dark_square_handle.clone(),
-        transform: Transform::from_xyz(board.get("a5").unwrap().x, board.get("a5").unwrap().y, sz),
-        ..default()
-    });
-    commands.spawn_bundle(SpriteBundle {
-        texture: assets.light_square_handle.clone(),
-        transform: Transform::from_xyz(board.get("b5").unwrap().x, board.get("b5").unwrap().y, sz),
-        ..default()
-    });
-    commands.spawn_bundle(SpriteBundle {
-        texture: assets.dark_square_handle.clone(),
-        transform: Transform::from_xyz(board.get("c5").unwrap().x, board.get("c5").unwrap().y, sz),
-        ..default()
-    });
-    commands.spawn_bundle(SpriteBundle {
-        texture: assets.light_square_handle.clone(),
-        transform: Transform::from_xyz(board.get("d5").unwrap().x, board.get("d5").unwrap().y, sz),
-        ..default()
-    });
-    commands.spawn_bundle(SpriteBundle {
-        texture: assets.dark_square_handle.clone(),
-        transform: Transform::from_xyz(board.get("e5").unwrap().x, board.get("e5").unwrap().y, sz),
-        ..default()
-    });
-    commands.spawn_bundle(SpriteBundle {
-        texture: assets.light_square_handle.clone(),
-        transform: Transform::from_xyz(board.get("f5").unwrap().x, board.get("f5").unwrap().y, sz),
-        ..default()
-    });
-    commands.spawn_bundle(SpriteBundle {
-        texture: assets.dark_square_handle.clone(),
-        transform: Transform::from_xyz(board.get("g5").unwrap().x, board.get("g5").unwrap().y, sz),
-        ..default()
-    });
-    commands.spawn_bundle(SpriteBundle {
-        texture: assets.light_square_handle.clone(),
-        transform: Transform::from_xyz(board.get("h5").unwrap().x, board.get("h5").unwrap().y, sz),
-        ..default()
-    });
-    // row 6
-    commands.spawn_bundle(SpriteBundle {
-        texture: assets.light_square_handle.clone(),
-        transform: Transform::from_xyz(board.get("a6").unwrap().x, board.get("a6").unwrap().y, sz),
-        ..default()
-    });
-    commands.spawn_bundle(SpriteBundle {
-        texture: assets.dark_square_handle.clone(),
-        transform: Transform::from_xyz(board.get("b6").unwrap().x, board.get("b6").unwrap().y, sz),
-        ..default()
-    });
-    commands.spawn_bundle(SpriteBundle {
-        texture: assets.light_square_handle.clone(),
-        transform: Transform::from_xyz(board.get("c6").unwrap().x, board.get("c6").unwrap().y, sz),
-        ..default()
-    });
-    commands.spawn_bundle(SpriteBundle {
-        texture: assets.dark_square_handle.clone(),
-        transform: Transform::from_xyz(board.get("d6").unwrap().x, board.get("d6").unwrap().y, sz),
-        ..default()
-    });
-    commands.spawn_bundle(SpriteBundle {
-        texture: assets.light_square_handle.clone(),
-        transform: Transform::from_xyz(board.get("e6").unwrap().x, board.get("e6").unwrap().y, sz),
-        ..default()
-    });
-    commands.spawn_bundle(SpriteBundle {
-        texture: assets.dark_square_handle.clone(),
-        transform: Transform::from_xyz(board.get("f6").unwrap().x, board.get("f6").unwrap().y, sz),
-        ..default()
-    });
-    commands.spawn_bundle(SpriteBundle {
-        texture: assets.light_square_handle.clone(),
-        transform: Transform::from_xyz(board.get("g6").unwrap().x, board.get("g6").unwrap().y, sz),
-        ..default()
-    });
-    commands.spawn_bundle(SpriteBundle {
-        texture: assets.dark_square_handle.clone(),
-        transform: Transform::from_xyz(board.get("h6").unwrap().x, board.get("h6").unwrap().y, sz),
-        ..default()
-    });
-    // row 7
-    commands.spawn_bundle(SpriteBundle {
-        texture: assets.dark_square_handle.clone(),
-        transform: Transform::from_xyz(board.get("a7").unwrap().x, board.get("a7").unwrap().y, sz),
-        ..default()
-    });
-    commands.spawn_bundle(SpriteBundle {
-        texture: assets.light_square_handle.clone(),
-        transform: Transform::from_xyz(board.get("b7").unwrap().x, board.get("b7").unwrap().y, sz),
-        ..default()
-    });
-    commands.spawn_bundle(SpriteBundle {
-        texture: assets.dark_square_handle.clone(),
-        transform: Transform::from_xyz(board.get("c7").unwrap().x, board.get("c7").unwrap().y, sz),
-        ..default()
-    });
-    commands.spawn_bundle(SpriteBundle {
-        texture: assets.light_square_handle.clone(),
-        transform: Transform::from_xyz(board.get("d7").unwrap().x, board.get("d7").unwrap().y, sz),
-        ..default()
-    });
-    commands.spawn_bundle(SpriteBundle {
-        texture: assets.dark_square_handle.clone(),
-        transform: Transform::from_xyz(board.get("e7").unwrap().x, board.get("e7").unwrap().y, sz),
-        ..default()
-    });
-    commands.spawn_bundle(SpriteBundle {
-        texture: assets.light_square_handle.clone(),
-        transform: Transform::from_xyz(board.get("f7").unwrap().x, board.get("f7").unwrap().y, sz),
-        ..default()
-    });
-    commands.spawn_bundle(SpriteBundle {
-        texture: assets.dark_square_handle.clone(),
-        transform: Transform::from_xyz(board.get("g7").unwrap().x, board.get("g7").unwrap().y, sz),
-        ..default()
-    });
-    commands.spawn_bundle(SpriteBundle {
-        texture: assets.light_square_handle.clone(),
-        transform: Transform::from_xyz(board.get("h7").unwrap().x, board.get("h7").unwrap().y, sz),
-        ..default()
-    });
-    // row 8
-    commands.spawn_bundle(SpriteBundle {
-        texture: assets.light_square_handle.clone(),
-        transform: Transform::from_xyz(board.get("a8").unwrap().x, board.get("a8").unwrap().y, sz),
-        ..default()
-    });
-    commands.spawn_bundle(SpriteBundle {
-        texture: assets.dark_square_handle.clone(),
-        transform: Transform::from_xyz(board.get("b8").unwrap().x, board.get("b8").unwrap().y, sz),
-        ..default()
-    });
-    commands.spawn_bundle(SpriteBundle {
-        texture: assets.light_square_handle.clone(),
-        transform: Transform::from_xyz(board.get("c8").unwrap().x, board.get("c8").unwrap().y, sz),
-        ..default()
-    });
-    commands.spawn_bundle(SpriteBundle {
-        texture: assets.dark_square_handle.clone(),
-        transform: Transform::from_xyz(board.get("d8").unwrap().x, board.get("d8").unwrap().y, sz),
-        ..default()
-    });
-    commands.spawn_bundle(SpriteBundle {
-        texture: assets.light_square_handle.clone(),
-        transform: Transform::from_xyz(board.get("e8").unwrap().x, board.get("e8").unwrap().y, sz),
-        ..default()
-    });
-    commands.spawn_bundle(SpriteBundle {
-        texture: assets.dark_square_handle.clone(),
-        transform: Transform::from_xyz(board.get("f8").unwrap().x, board.get("f8").unwrap().y, sz),
-        ..default()
-    });
-    commands.spawn_bundle(SpriteBundle {
-        texture: assets.light_square_handle.clone(),
-        transform: Transform::from_xyz(board.get("g8").unwrap().x, board.get("g8").unwrap().y, sz),
-        ..default()
-    });
-    commands.spawn_bundle(SpriteBundle {
-        texture: assets.dark_square_handle.clone(),
-        transform: Transform::from_xyz(board.get("h8").unwrap().x, board.get("h8").unwrap().y, sz),
-        ..default()
-    });
+    for (label, mut transform, mut visibility) in label_query.iter_mut() {
+        let pos = label.position(&layout);
+        transform.translation = pos.extend(transform.translation.z);
+        visibility.is_visible = settings.show_coordinates;
+    }
 }
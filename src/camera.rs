@@ -1,16 +1,21 @@
 use bevy::prelude::*;
 use bevy_mod_picking::*;
 
-use crate::state::ChessState;
+use crate::{board::BoardLayout, state::ChessState};
 
 #[derive(Component)]
 pub struct ChessCamera;
 
-pub fn setup(mut commands: Commands, mut state: ResMut<State<ChessState>>) {
+pub fn setup(
+    mut commands: Commands,
+    layout: Res<BoardLayout>,
+    mut state: ResMut<State<ChessState>>,
+) {
+    let center = layout.view_center();
     commands
         .spawn()
         .insert_bundle(Camera2dBundle {
-            transform: Transform::from_xyz(175.0, 175.0, 10.0),
+            transform: Transform::from_xyz(center.x, center.y, 10.0),
             ..default()
         })
         .insert_bundle(PickingCameraBundle::default())
@@ -1,5 +1,6 @@
-use crate::assets::TextAssets;
+use crate::{assets::TextAssets, board::BoardLayout};
 use bevy::prelude::*;
+use bevy_egui::{egui, EguiContext};
 use indoc::indoc;
 
 #[derive(Component)]
@@ -8,18 +9,28 @@ pub struct ControlsText;
 #[derive(Component)]
 pub struct TipsElement;
 
-pub fn spawn(mut commands: Commands, text_assets: Res<TextAssets>) {
+// where the controls sit in the reference window, above the board
+const TIPS_LEFT: f32 = 30.0;
+const TIPS_BOTTOM: f32 = 570.0;
+const TIPS_FONT_SIZE: f32 = 14.0;
+
+pub fn spawn(mut commands: Commands, text_assets: Res<TextAssets>, layout: Res<BoardLayout>) {
     let clear_color_hex_string = "69696b";
     let tips_text = indoc! {"
         controls
         --------------------
-        left mouse click: pickup / place a piece
-        x: remove a selected piece | shift + x: clear the board
-        s: save position (overwrites) | shift + s: clear saved position
-        r: restore saved position on the board
-        cmd + c: copy current FEN to clipboard
-        i: all pieces in 'initial' / starting positions
+        left mouse click: pickup / place a piece, or edit the current FEN + enter
+        x: remove a selected piece | shift + x: clear the board | i: starting positions
+        s: save position | shift + s: remove last saved | right click saved: rename
+        1-9: restore saved position | shift + 1-9: save to slot | r: restore last saved
+        t: toggle side to move | k, q: black castling | shift + k, q: white castling
+        cmd / ctrl + c: copy current FEN to clipboard | cmd / ctrl + v: paste a FEN onto the board
+        cmd / ctrl + z: undo | cmd / ctrl + shift + z, cmd / ctrl + y: redo
+        f: flip the board | l: show / hide coordinates | p: switch between setup and play
+        m: show / hide where the piece in hand can go while setting up
+        left, right: step back / forward through the moves | home, end: first / last move
     "};
+    let offset = layout.ui_pixels(TIPS_LEFT, TIPS_BOTTOM);
     commands
         .spawn_bundle(NodeBundle {
             style: Style {
@@ -28,8 +39,8 @@ pub fn spawn(mut commands: Commands, text_assets: Res<TextAssets>) {
                 justify_content: JustifyContent::FlexStart,
                 align_items: AlignItems::FlexStart,
                 position: UiRect {
-                    left: Val::Px(30.0),
-                    bottom: Val::Px(570.0),
+                    left: Val::Px(offset.x),
+                    bottom: Val::Px(offset.y),
                     ..default()
                 },
                 ..default()
@@ -50,7 +61,7 @@ pub fn spawn(mut commands: Commands, text_assets: Res<TextAssets>) {
                             value: tips_text.to_string(),
                             style: TextStyle {
                                 font: text_assets.regular_font_handle.clone(),
-                                font_size: 14.0,
+                                font_size: TIPS_FONT_SIZE * layout.scale,
                                 color: Color::rgb(0.15, 0.15, 0.15),
                             },
                         }],
@@ -64,3 +75,43 @@ pub fn spawn(mut commands: Commands, text_assets: Res<TextAssets>) {
                 .insert(ControlsText);
         });
 }
+
+/// keep the controls above the board as the window changes size
+pub fn follow_layout(
+    layout: Res<BoardLayout>,
+    mut tips_query: Query<&mut Style, With<TipsElement>>,
+    mut text_query: Query<&mut Text, With<ControlsText>>,
+) {
+    if !layout.is_changed() {
+        return;
+    }
+
+    let offset = layout.ui_pixels(TIPS_LEFT, TIPS_BOTTOM);
+    for mut style in tips_query.iter_mut() {
+        style.position.left = Val::Px(offset.x);
+        style.position.bottom = Val::Px(offset.y);
+    }
+    for mut text in text_query.iter_mut() {
+        for section in text.sections.iter_mut() {
+            section.style.font_size = TIPS_FONT_SIZE * layout.scale;
+        }
+    }
+}
+
+pub fn setup_egui(mut egui_context: ResMut<EguiContext>) {
+    // dark text on light fields, closer to the rest of the board than egui's dark theme
+    egui_context.ctx_mut().set_visuals(egui::Visuals::light());
+}
+
+pub fn block_keys_while_typing(
+    mut egui_context: ResMut<EguiContext>,
+    mut keys: ResMut<Input<KeyCode>>,
+) {
+    // typing a FEN shouldn't also clear the board, save positions, etc.
+    if egui_context.ctx_mut().wants_keyboard_input() {
+        let pressed: Vec<KeyCode> = keys.get_pressed().copied().collect();
+        for key in pressed {
+            keys.reset(key);
+        }
+    }
+}
@@ -0,0 +1,387 @@
+use crate::{
+    board::BoardLayout,
+    engine_settings::EngineSettingsWindow,
+    lines::{line_controls, LinePreview},
+    persist::Settings,
+    pieces::Piece,
+    play::{Mode, PlayMove},
+    types::WithSelectedPiece,
+    validation::PositionProblems,
+};
+use bevy::prelude::*;
+use bevy_egui::{egui, EguiContext};
+use chessbored::{
+    position::{Position, Turn},
+    uci::{Engine, EngineMessage, Info, Score},
+};
+use std::collections::BTreeMap;
+
+// how long the board has to stay the same before the engine's sent it, so a burst of
+// edits or moves clicked through doesn't restart the search every frame
+const SETTLE_SECONDS: f64 = 0.3;
+
+// the most lines the engine can be asked for from the panel
+const MAX_LINES: u32 = 5;
+
+/// the engine that's been started, if there is one, and what it's found
+#[derive(Component, Default)]
+pub struct EngineState {
+    pub engine: Option<Engine>,
+    // the position it was last sent, its lines are played out from here
+    pub analysed: Option<Position>,
+    // the best lines it's found so far, the best first
+    pub lines: Vec<Info>,
+    // how fast it's searching, in nodes per second
+    pub nps: Option<u64>,
+    // the FEN of a position that wasn't sent because it has problems
+    pub skipped: Option<String>,
+    // stopped from the panel, the board isn't followed until it's started again
+    pub paused: bool,
+    // why the engine couldn't be started or stopped running
+    pub error: Option<String>,
+}
+
+impl EngineState {
+    /// start the engine at `path` with the options it's been given, dropping any that's
+    /// running
+    pub fn start(&mut self, path: &str, lines: u32, options: &BTreeMap<String, String>) {
+        *self = match Engine::start(path) {
+            Ok(engine) => EngineState {
+                engine: Some(engine),
+                ..default()
+            },
+            Err(e) => {
+                warn!("couldn't start {}: {}", path, e);
+                EngineState {
+                    error: Some(format!("couldn't start the engine: {}", e)),
+                    ..default()
+                }
+            }
+        };
+        self.set_lines(lines);
+        for (name, value) in options {
+            self.set_option(name, value);
+        }
+    }
+
+    // ask for `lines` lines
+    fn set_lines(&mut self, lines: u32) {
+        self.set_option("MultiPV", &lines.to_string());
+    }
+
+    /// change one of the engine's options, the search starts over with it once the
+    /// board's next looked at
+    pub fn set_option(&mut self, name: &str, value: &str) {
+        self.configure(|engine| engine.set_option(name, value));
+    }
+
+    /// press one of the engine's button options
+    pub fn press(&mut self, name: &str) {
+        self.configure(|engine| engine.press(name));
+    }
+
+    fn configure(&mut self, change: impl FnOnce(&mut Engine) -> std::io::Result<()>) {
+        let result = match self.engine.as_mut() {
+            Some(engine) => change(engine),
+            None => return,
+        };
+        match result {
+            Ok(()) => {
+                self.analysed = None;
+                self.lines.clear();
+            }
+            Err(e) => self.lost(e.to_string()),
+        }
+    }
+
+    fn analyse(&mut self, position: &Position) {
+        let result = match self.engine.as_mut() {
+            Some(engine) => engine.analyse(position),
+            None => return,
+        };
+        match result {
+            Ok(()) => {
+                self.analysed = Some(position.clone());
+                self.lines.clear();
+                self.nps = None;
+                self.skipped = None;
+            }
+            Err(e) => self.lost(e.to_string()),
+        }
+    }
+
+    fn stop(&mut self) {
+        if let Some(Err(e)) = self.engine.as_mut().map(Engine::stop) {
+            self.lost(e.to_string());
+        }
+    }
+
+    // leave a position the engine wouldn't make sense of, and say so in the panel
+    fn skip(&mut self, fen: String) {
+        self.stop();
+        self.analysed = None;
+        self.lines.clear();
+        self.skipped = Some(fen);
+    }
+
+    fn pause(&mut self) {
+        self.stop();
+        self.paused = true;
+    }
+
+    // the board's picked up again the next time it's looked at
+    fn resume(&mut self) {
+        self.paused = false;
+        self.analysed = None;
+        self.skipped = None;
+    }
+
+    // the engine's gone away, or can't be written to
+    fn lost(&mut self, reason: String) {
+        warn!("lost the engine: {}", reason);
+        *self = EngineState {
+            error: Some(format!("the engine stopped: {}", reason)),
+            ..default()
+        };
+    }
+}
+
+/// an engine's score from white's side, "+0.35" or "#-3"
+pub fn score_text(score: &Score, turn: &Turn) -> String {
+    let sign = match turn {
+        Turn::White => 1,
+        Turn::Black => -1,
+    };
+    match score {
+        Score::Centipawns(cp) => format!("{:+.2}", (cp * sign) as f32 / 100.0),
+        Score::Mate(moves) => format!("#{}", moves * sign),
+    }
+}
+
+/// send the engine the position on the board once it's settled, whether it got there
+/// by setting up or playing, stopping whatever it was looking at before
+pub fn follow_board(
+    time: Res<Time>,
+    position: Res<Position>,
+    position_problems: Res<PositionProblems>,
+    mut state: ResMut<EngineState>,
+    selected_query: Query<&Piece, WithSelectedPiece>,
+    // a position that's waiting to settle, and since when
+    mut settling: Local<Option<(String, f64)>>,
+) {
+    // a piece in hand has left the board while setting up, so the position isn't
+    // finished until it's put down
+    if state.engine.is_none() || state.paused || !selected_query.is_empty() {
+        *settling = None;
+        return;
+    }
+
+    let fen = position.to_fen();
+    let handled = state.skipped.as_ref() == Some(&fen)
+        || state
+            .analysed
+            .as_ref()
+            .is_some_and(|analysed| analysed.to_fen() == fen);
+    if handled {
+        *settling = None;
+        return;
+    }
+
+    let now = time.seconds_since_startup();
+    let since = match &*settling {
+        Some((waiting, since)) if *waiting == fen => *since,
+        _ => {
+            *settling = Some((fen, now));
+            return;
+        }
+    };
+    if now - since < SETTLE_SECONDS {
+        return;
+    }
+
+    *settling = None;
+    if position_problems.problems.is_empty() {
+        state.analyse(&position);
+    } else {
+        state.skip(fen);
+    }
+}
+
+/// pick up what the engine's said since the last frame
+pub fn read_engine(mut state: ResMut<EngineState>) {
+    let engine = match state.engine.as_mut() {
+        Some(engine) => engine,
+        None => return,
+    };
+
+    let messages = match engine.messages() {
+        Ok(messages) => messages,
+        Err(e) => {
+            state.lost(e.to_string());
+            return;
+        }
+    };
+    if engine.has_exited() {
+        state.lost("it quit".to_string());
+        return;
+    }
+
+    for message in messages {
+        if let EngineMessage::Info(info) = message {
+            if info.nps.is_some() {
+                state.nps = info.nps;
+            }
+            // some info is only about the search as a whole
+            if info.pv.is_empty() {
+                continue;
+            }
+            let index = info.multipv.unwrap_or(1).max(1) as usize - 1;
+            if state.lines.len() <= index {
+                state.lines.resize(index + 1, Info::default());
+            }
+            state.lines[index] = info;
+        }
+    }
+}
+
+/// starting and stopping an engine, and the lines it's found, below the moves in the
+/// side panel column
+pub fn engine_panel(
+    mut egui_context: ResMut<EguiContext>,
+    layout: Res<BoardLayout>,
+    position: Res<Position>,
+    mut settings: ResMut<Settings>,
+    mut state: ResMut<EngineState>,
+    mut preview: ResMut<LinePreview>,
+    mut settings_window: ResMut<EngineSettingsWindow>,
+    mut mode: ResMut<Mode>,
+    mut play_events: EventWriter<PlayMove>,
+    selected_query: Query<&Piece, WithSelectedPiece>,
+) {
+    let text_color = egui::Color32::from_rgb(0x26, 0x26, 0x26);
+    let error_color = egui::Color32::from_rgb(0x7a, 0x1f, 0x1f);
+
+    // edited on the side so settings are only written when the path really changes
+    let mut path = settings.engine_path.clone();
+    let mut lines = settings.engine_lines;
+    let running = state.engine.is_some();
+    // lines for a position that's no longer on the board can't be previewed or played,
+    // and nor can they with a piece in hand, since playing them switches to play mode
+    let on_board = selected_query.is_empty()
+        && state
+            .analysed
+            .as_ref()
+            .is_some_and(|analysed| analysed.to_fen() == position.to_fen());
+    let mut start = false;
+    let mut quit = false;
+    let mut analyse = false;
+    let mut stop = false;
+
+    let offset = layout.ui_points(layout.panel_left() + 10.0, 20.0);
+    let choice = egui::Area::new("engine")
+        .anchor(egui::Align2::LEFT_BOTTOM, [offset.x, -offset.y])
+        .show(egui_context.ctx_mut(), |ui| {
+            ui.set_width(layout.panel_width - 20.0);
+            ui.label(
+                egui::RichText::new("engine\n--------------------")
+                    .monospace()
+                    .color(text_color),
+            );
+            ui.add_enabled(
+                !running,
+                egui::TextEdit::singleline(&mut path)
+                    .hint_text("path to a UCI engine")
+                    .desired_width(f32::INFINITY),
+            );
+            ui.horizontal(|ui| {
+                if running {
+                    quit = ui.button("quit").clicked();
+                    if state.paused {
+                        analyse = ui.button("analyse").clicked();
+                    } else {
+                        stop = ui.button("stop").clicked();
+                    }
+                } else {
+                    ui.add_enabled_ui(!path.trim().is_empty(), |ui| {
+                        start = ui.button("start").clicked();
+                    });
+                }
+                ui.label(egui::RichText::new("lines").monospace().color(text_color));
+                ui.add(egui::DragValue::new(&mut lines).clamp_range(1..=MAX_LINES));
+            });
+            ui.horizontal(|ui| {
+                if ui.button("settings").clicked() {
+                    settings_window.open = !settings_window.open;
+                }
+                if let Some(name) = state
+                    .engine
+                    .as_ref()
+                    .and_then(|engine| engine.name.as_ref())
+                {
+                    ui.label(egui::RichText::new(name).monospace().color(text_color));
+                }
+            });
+
+            let choice = line_controls(ui, &state, on_board, text_color);
+            if state.skipped.is_some() && !state.paused {
+                ui.label(
+                    egui::RichText::new("waiting for a position without problems")
+                        .monospace()
+                        .color(text_color),
+                );
+            }
+            if let Some(error) = &state.error {
+                ui.label(egui::RichText::new(error).monospace().color(error_color));
+            }
+            choice
+        })
+        .inner;
+
+    // the hovered move's position stands in for the board until the pointer moves off it
+    let previewed = match (choice.hovered, &state.analysed) {
+        (Some(moves), Some(analysed)) => {
+            let mut previewed = analysed.clone();
+            for mv in &moves {
+                previewed.play(mv);
+            }
+            Some(previewed)
+        }
+        _ => None,
+    };
+    if preview.0 != previewed {
+        preview.0 = previewed;
+    }
+    if let Some(moves) = choice.clicked {
+        // moves are only played, and kept in the history, in play mode
+        if *mode != Mode::Play {
+            *mode = Mode::Play;
+        }
+        for mv in moves {
+            play_events.send(PlayMove(mv));
+        }
+    }
+
+    if path != settings.engine_path {
+        settings.engine_path = path;
+    }
+    if lines != settings.engine_lines {
+        settings.engine_lines = lines;
+        state.set_lines(lines);
+    }
+    if start {
+        state.start(
+            settings.engine_path.trim(),
+            settings.engine_lines,
+            &settings.current_engine_options(),
+        );
+    }
+    if quit {
+        *state = EngineState::default();
+    }
+    if analyse {
+        state.resume();
+    }
+    if stop {
+        state.pause();
+    }
+}
@@ -0,0 +1,271 @@
+use crate::{board::BoardLayout, engine::EngineState, persist::Settings};
+use bevy::prelude::*;
+use bevy_egui::{egui, EguiContext};
+use chessbored::uci::{EngineOption, OptionKind};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+// options that are set from somewhere else, the number of lines is in the engine panel
+const PANEL_OPTIONS: [&str; 1] = ["MultiPV"];
+
+/// an engine and how someone likes it set up, saved under a name
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EngineProfile {
+    pub name: String,
+    pub path: String,
+    pub lines: u32,
+    pub options: BTreeMap<String, String>,
+}
+
+/// whether the engine settings are open, and what's being typed into them
+#[derive(Component, Default)]
+pub struct EngineSettingsWindow {
+    pub open: bool,
+    // what to save the current setup as
+    pub profile_name: String,
+    // values still being typed or dragged, they're sent to the engine once they're done
+    edits: BTreeMap<String, String>,
+}
+
+/// what's been asked for in the window
+enum Change {
+    Set(String, String),
+    Press(String),
+    Reset,
+    Save,
+    Load(usize),
+    Delete(usize),
+}
+
+// the widget for `option` showing `value`, and what to send once it's been changed
+fn option_widget(
+    ui: &mut egui::Ui,
+    option: &EngineOption,
+    value: &str,
+    edits: &mut BTreeMap<String, String>,
+) -> Option<Change> {
+    let name = &option.name;
+    let set = |value: String| Some(Change::Set(name.clone(), value));
+
+    match &option.kind {
+        OptionKind::Check { .. } => {
+            let mut checked = value == "true";
+            if ui.checkbox(&mut checked, "").changed() {
+                return set(checked.to_string());
+            }
+        }
+        OptionKind::Spin { default, min, max } => {
+            let mut number: i64 = edits
+                .get(name)
+                .map_or(value, String::as_str)
+                .parse()
+                .unwrap_or(*default);
+            let response = ui.add(egui::DragValue::new(&mut number).clamp_range(*min..=*max));
+            if response.changed() {
+                edits.insert(name.clone(), number.to_string());
+            }
+            // the engine's only told once the value's let go of
+            if (response.changed() && !response.dragged()) || response.drag_released() {
+                return edits.remove(name).and_then(set);
+            }
+        }
+        OptionKind::Combo { choices, .. } => {
+            let mut chosen = None;
+            egui::ComboBox::from_id_source(name)
+                .selected_text(value)
+                .show_ui(ui, |ui| {
+                    for choice in choices {
+                        if ui.selectable_label(choice == value, choice).clicked() {
+                            chosen = Some(choice.clone());
+                        }
+                    }
+                });
+            return chosen.and_then(set);
+        }
+        OptionKind::Button => {
+            if ui.button("press").clicked() {
+                return Some(Change::Press(name.clone()));
+            }
+        }
+        OptionKind::String { .. } => {
+            let mut text = edits.get(name).map_or(value, String::as_str).to_string();
+            let response = ui.add(egui::TextEdit::singleline(&mut text).desired_width(140.0));
+            if response.changed() {
+                edits.insert(name.clone(), text);
+            }
+            if response.lost_focus() {
+                return edits.remove(name).and_then(set);
+            }
+        }
+    }
+    None
+}
+
+/// the options the running engine offers, each with a widget that suits it, and the
+/// saved engine profiles to switch between
+pub fn engine_settings_window(
+    mut egui_context: ResMut<EguiContext>,
+    layout: Res<BoardLayout>,
+    mut settings: ResMut<Settings>,
+    mut state: ResMut<EngineState>,
+    mut window: ResMut<EngineSettingsWindow>,
+) {
+    if !window.open {
+        return;
+    }
+
+    let text_color = egui::Color32::from_rgb(0x26, 0x26, 0x26);
+    let mut open = true;
+    let mut changes = vec![];
+    let EngineSettingsWindow {
+        profile_name,
+        edits,
+        ..
+    } = &mut *window;
+
+    let offset = layout.ui_points(60.0, 60.0);
+    egui::Window::new("engine settings")
+        .open(&mut open)
+        .default_pos([offset.x, offset.y])
+        .resizable(false)
+        .show(egui_context.ctx_mut(), |ui| {
+            ui.label(
+                egui::RichText::new("profiles\n--------------------")
+                    .monospace()
+                    .color(text_color),
+            );
+            ui.horizontal_wrapped(|ui| {
+                for (index, profile) in settings.engine_profiles.iter().enumerate() {
+                    let response = ui.button(&profile.name).on_hover_text(&profile.path);
+                    if response.clicked() {
+                        changes.push(Change::Load(index));
+                    }
+                    response.context_menu(|ui| {
+                        if ui.button("delete").clicked() {
+                            changes.push(Change::Delete(index));
+                            ui.close_menu();
+                        }
+                    });
+                }
+            });
+            ui.horizontal(|ui| {
+                ui.add(
+                    egui::TextEdit::singleline(profile_name)
+                        .hint_text("profile name")
+                        .desired_width(140.0),
+                );
+                let named = !profile_name.trim().is_empty();
+                if ui.add_enabled(named, egui::Button::new("save")).clicked() {
+                    changes.push(Change::Save);
+                }
+            });
+
+            ui.label(
+                egui::RichText::new("options\n--------------------")
+                    .monospace()
+                    .color(text_color),
+            );
+            let changed = settings.current_engine_options();
+            let options = match &state.engine {
+                Some(engine) if !engine.options.is_empty() => &engine.options,
+                Some(_) => {
+                    ui.label(egui::RichText::new("this engine has no options").color(text_color));
+                    return;
+                }
+                None => {
+                    ui.label(
+                        egui::RichText::new("start an engine to see its options").color(text_color),
+                    );
+                    return;
+                }
+            };
+
+            egui::ScrollArea::vertical()
+                .max_height(360.0)
+                .show(ui, |ui| {
+                    egui::Grid::new("engine options")
+                        .num_columns(2)
+                        .show(ui, |ui| {
+                            for option in options
+                                .iter()
+                                .filter(|option| !PANEL_OPTIONS.contains(&option.name.as_str()))
+                            {
+                                let value = changed
+                                    .get(&option.name)
+                                    .cloned()
+                                    .unwrap_or_else(|| option.kind.default_value());
+                                ui.label(
+                                    egui::RichText::new(&option.name)
+                                        .monospace()
+                                        .color(text_color),
+                                );
+                                changes.extend(option_widget(ui, option, &value, edits));
+                                ui.end_row();
+                            }
+                        });
+                });
+            if ui.button("reset to defaults").clicked() {
+                changes.push(Change::Reset);
+            }
+        });
+    window.open = open;
+
+    for change in changes {
+        match change {
+            Change::Set(name, value) => {
+                state.set_option(&name, &value);
+                settings.current_engine_options_mut().insert(name, value);
+            }
+            Change::Press(name) => state.press(&name),
+            Change::Reset => {
+                let changed = settings.current_engine_options_mut();
+                let defaults: Vec<(String, String)> = state
+                    .engine
+                    .iter()
+                    .flat_map(|engine| engine.options.iter())
+                    .filter(|option| changed.contains_key(&option.name))
+                    .map(|option| (option.name.clone(), option.kind.default_value()))
+                    .collect();
+                changed.clear();
+                for (name, value) in defaults {
+                    state.set_option(&name, &value);
+                }
+            }
+            Change::Save => {
+                let profile = EngineProfile {
+                    name: window.profile_name.trim().to_string(),
+                    path: settings.engine_path.clone(),
+                    lines: settings.engine_lines,
+                    options: settings.current_engine_options(),
+                };
+                match settings
+                    .engine_profiles
+                    .iter_mut()
+                    .find(|saved| saved.name == profile.name)
+                {
+                    Some(saved) => *saved = profile,
+                    None => settings.engine_profiles.push(profile),
+                }
+            }
+            Change::Load(index) => {
+                let profile = match settings.engine_profiles.get(index) {
+                    Some(profile) => profile.clone(),
+                    None => continue,
+                };
+                // a running engine's swapped for the profile's, set up the profile's way
+                if state.engine.is_some() {
+                    state.start(&profile.path, profile.lines, &profile.options);
+                }
+                settings.engine_path = profile.path;
+                settings.engine_lines = profile.lines;
+                *settings.current_engine_options_mut() = profile.options;
+                window.profile_name = profile.name;
+            }
+            Change::Delete(index) => {
+                if index < settings.engine_profiles.len() {
+                    settings.engine_profiles.remove(index);
+                }
+            }
+        }
+    }
+}
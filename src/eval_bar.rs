@@ -0,0 +1,123 @@
+use crate::{board::BoardLayout, engine::EngineState};
+use bevy::prelude::*;
+use chessbored::{position::Turn, uci::Score};
+use std::cmp::Ordering;
+
+// how quickly the bar closes the gap to a new score, per second
+const FOLLOW_RATE: f32 = 6.0;
+
+// how far a centipawn moves the bar, a pawn up fills about 60% of it
+const SCORE_SLOPE: f32 = 0.004;
+
+/// the bar to the left of the board showing how the engine rates the position, white's
+/// share filling it from white's side
+#[derive(Component)]
+pub struct EvalBar;
+
+/// white's share of the eval bar, drawn over the rest of it
+#[derive(Component)]
+pub struct EvalBarFill;
+
+/// how much of the bar is white's for `score`, given by the engine for the side to move
+/// in `turn`. a mate fills it all, otherwise an even position sits in the middle and it
+/// gets harder to move the further it's gone
+pub fn white_share(score: &Score, turn: &Turn) -> f32 {
+    let for_white = |value: i32| match turn {
+        Turn::White => value,
+        Turn::Black => -value,
+    };
+    match score {
+        Score::Mate(moves) => match for_white(*moves).cmp(&0) {
+            Ordering::Greater => 1.0,
+            Ordering::Less => 0.0,
+            // mated already, so it's the side to move that's lost
+            Ordering::Equal if *turn == Turn::White => 0.0,
+            Ordering::Equal => 1.0,
+        },
+        Score::Centipawns(cp) => 1.0 / (1.0 + (-SCORE_SLOPE * for_white(*cp) as f32).exp()),
+    }
+}
+
+pub fn setup_eval_bar(mut commands: Commands, layout: Res<BoardLayout>) {
+    let center = layout.eval_bar_center();
+    let size = layout.eval_bar_size();
+    commands
+        .spawn_bundle(SpriteBundle {
+            sprite: Sprite {
+                color: Color::rgb(0.25, 0.25, 0.25),
+                custom_size: Some(size),
+                ..default()
+            },
+            transform: Transform::from_xyz(center.x, center.y, -0.01),
+            visibility: Visibility { is_visible: false },
+            ..default()
+        })
+        .insert(EvalBar);
+    commands
+        .spawn_bundle(SpriteBundle {
+            sprite: Sprite {
+                color: Color::rgb(0.92, 0.92, 0.92),
+                custom_size: Some(Vec2::new(size.x, size.y / 2.0)),
+                ..default()
+            },
+            transform: Transform::from_xyz(center.x, center.y, -0.009),
+            visibility: Visibility { is_visible: false },
+            ..default()
+        })
+        .insert(EvalBar)
+        .insert(EvalBarFill);
+}
+
+/// ease the bar toward the engine's latest score for the best line, from white's end
+/// of the board whichever way up it is. there's no bar without an engine
+pub fn update_eval_bar(
+    time: Res<Time>,
+    layout: Res<BoardLayout>,
+    state: Res<EngineState>,
+    mut bar_query: Query<
+        (
+            &mut Visibility,
+            &mut Sprite,
+            &mut Transform,
+            Option<&EvalBarFill>,
+        ),
+        With<EvalBar>,
+    >,
+    // white's share as it's drawn right now
+    mut shown: Local<Option<f32>>,
+) {
+    let visible = state.engine.is_some();
+    // an even bar until there's a score, and the last one while waiting for the next
+    let target = match (&state.analysed, state.lines.first()) {
+        (Some(analysed), Some(line)) => line.score.map(|score| white_share(&score, &analysed.turn)),
+        _ => None,
+    };
+    let share = match (*shown, target) {
+        (None, target) => target.unwrap_or(0.5),
+        (Some(shown), Some(target)) => {
+            shown + (target - shown) * (time.delta_seconds() * FOLLOW_RATE).min(1.0)
+        }
+        (Some(shown), None) => shown,
+    };
+    *shown = visible.then_some(share);
+
+    let center = layout.eval_bar_center();
+    let size = layout.eval_bar_size();
+    for (mut visibility, mut sprite, mut transform, fill) in bar_query.iter_mut() {
+        visibility.is_visible = visible;
+        if fill.is_none() {
+            continue;
+        }
+
+        let height = size.y * share;
+        // white's end of the bar is whichever end white's pieces start at
+        let white_end = if layout.flipped {
+            center.y + size.y / 2.0 - height / 2.0
+        } else {
+            center.y - size.y / 2.0 + height / 2.0
+        };
+        sprite.custom_size = Some(Vec2::new(size.x, height));
+        transform.translation.x = center.x;
+        transform.translation.y = white_end;
+    }
+}
@@ -0,0 +1,126 @@
+use crate::history::MoveHistory;
+use bevy::prelude::*;
+use bevy_egui::{egui, EguiClipboard};
+use chessbored::{
+    game::Game,
+    pgn::{seven_tag_roster, write_pgn, Tag, SEVEN_TAG_ROSTER},
+    position::Turn,
+};
+use std::fs;
+
+/// the tags to write at the top of an exported game, and where to save it
+#[derive(Component)]
+pub struct PgnExport {
+    pub tags: Vec<Tag>,
+    // where games are saved to, and loaded from
+    pub path: String,
+    // what happened to the last copy or save
+    pub status: Option<String>,
+}
+
+impl Default for PgnExport {
+    fn default() -> Self {
+        let path = dirs::home_dir()
+            .map(|dir| dir.join("chessbored.pgn").display().to_string())
+            .unwrap_or_else(|| "chessbored.pgn".to_string());
+        PgnExport {
+            tags: seven_tag_roster(),
+            path,
+            status: None,
+        }
+    }
+}
+
+impl PgnExport {
+    /// the recorded game as PGN. a game that's over says how, unless the result's
+    /// been filled in by hand
+    pub fn pgn(&self, history: &MoveHistory) -> String {
+        let mut game = history.game.clone();
+        game.tags = self.tags.clone();
+
+        let end = game.mainline_from(&[]);
+        let last = game.position_at(&end).unwrap_or_else(|| game.start.clone());
+        let result = if last.is_checkmate() {
+            match last.turn {
+                Turn::White => "0-1",
+                Turn::Black => "1-0",
+            }
+        } else if last.is_stalemate() {
+            "1/2-1/2"
+        } else {
+            "*"
+        };
+        for tag in game.tags.iter_mut() {
+            if tag.name == "Result" && tag.value == "*" {
+                tag.value = result.to_string();
+            }
+        }
+
+        write_pgn(&game)
+    }
+
+    /// fill the tags in from a game that's been loaded, the position ones are worked
+    /// out again on the way out
+    pub fn take_tags(&mut self, game: &Game) {
+        let mut tags = seven_tag_roster();
+        for tag in tags.iter_mut() {
+            if let Some(value) = game.tag(&tag.name) {
+                tag.value = value.to_string();
+            }
+        }
+        tags.extend(game.tags.iter().cloned().filter(|tag| {
+            !SEVEN_TAG_ROSTER.contains(&tag.name.as_str())
+                && tag.name != "SetUp"
+                && tag.name != "FEN"
+        }));
+        self.tags = tags;
+    }
+}
+
+/// copy and save buttons for the recorded game, and its tags to fill in, for the
+/// side panel
+pub fn export_controls(
+    ui: &mut egui::Ui,
+    history: &MoveHistory,
+    export: &mut PgnExport,
+    clipboard: &mut EguiClipboard,
+    text_color: egui::Color32,
+) {
+    ui.horizontal(|ui| {
+        if ui.button("copy pgn").clicked() {
+            clipboard.set_contents(&export.pgn(history));
+            export.status = Some("copied".to_string());
+        }
+        if ui.button("save pgn").clicked() {
+            export.status = Some(match fs::write(&export.path, export.pgn(history)) {
+                Ok(()) => "saved".to_string(),
+                Err(e) => {
+                    warn!("couldn't write {}: {}", export.path, e);
+                    e.to_string()
+                }
+            });
+        }
+        if let Some(status) = &export.status {
+            ui.label(egui::RichText::new(status).monospace().color(text_color));
+        }
+    });
+    ui.add(
+        egui::TextEdit::singleline(&mut export.path)
+            .hint_text("save to")
+            .desired_width(f32::INFINITY),
+    );
+
+    egui::CollapsingHeader::new(egui::RichText::new("tags").color(text_color))
+        .id_source("pgn tags")
+        .show(ui, |ui| {
+            egui::Grid::new("pgn tag values")
+                .num_columns(2)
+                .show(ui, |ui| {
+                    for tag in export.tags.iter_mut() {
+                        ui.label(egui::RichText::new(&tag.name).monospace().color(text_color));
+                        ui.add(egui::TextEdit::singleline(&mut tag.value).desired_width(120.0));
+                        ui.end_row();
+                    }
+                });
+        });
+}
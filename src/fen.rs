@@ -1,463 +1,522 @@
 use crate::{
-    assets::TextAssets,
-    board::{get_square, File, Rank, Square},
-    pieces::{place_piece, ActivePiece, Kind, Piece, PieceMaterialHandles, Side},
-    types::{Board, WithActivePiece, WithFenText},
+    board::BoardLayout,
+    pieces::{place_piece, ActivePiece, Piece, PieceMaterialHandles},
+    play::{mode_controls, Mode, Promotion},
+    types::{WithActivePiece, WithSelectedPiece},
+    validation::PositionProblems,
 };
 use bevy::prelude::*;
+use bevy_egui::{egui, EguiContext};
 use bevy_mod_picking::PickableMesh;
-use indoc::indoc;
-use std::fmt::Write as _;
+use chessbored::position::{FenError, Position};
+use serde::{Deserialize, Serialize};
 
-#[derive(Component)]
-pub struct FenElement;
-
-#[derive(Component)]
-pub struct FenText;
-
-#[derive(Component)]
-pub struct CopyElement;
-
-#[derive(Component)]
-pub struct CopyText;
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SavedPosition {
+    pub name: String,
+    pub fen: String,
+}
 
-#[derive(Component)]
+#[derive(Component, Default)]
 pub struct SavedFenState {
-    pub text_entity: Option<Entity>,
     pub curr: String,
-    pub saved: String,
+    pub saved: Vec<SavedPosition>,
+    // keeps default names unique as positions come and go
+    pub saved_count: usize,
 }
 
-impl Default for SavedFenState {
-    fn default() -> Self {
-        SavedFenState {
-            text_entity: None,
-            curr: "".into(),
-            saved: "".into(),
+impl SavedFenState {
+    pub fn save(&mut self, fen: String) {
+        self.saved_count += 1;
+        self.saved.push(SavedPosition {
+            name: format!("position {}", self.saved_count),
+            fen,
+        });
+    }
+
+    /// overwrite the position in `slot`, or add a new one if there's nothing there yet
+    pub fn save_to(&mut self, slot: usize, fen: String) {
+        match self.saved.get_mut(slot) {
+            Some(saved) => saved.fen = fen,
+            None => self.save(fen),
         }
     }
 }
 
-pub fn toggle_save_position(
-    mut commands: Commands,
-    mut saved_fen: ResMut<SavedFenState>,
-    fen_assets: Res<TextAssets>,
+pub fn toggle_fen_fields(
+    mut position: ResMut<Position>,
     keys: Res<Input<KeyCode>>,
+    mode: Res<Mode>,
 ) {
-    let clear_color_hex_string = "69696b";
-    let text_color_hex_string = "a1a1a1";
-
-    if (keys.pressed(KeyCode::RShift) || keys.pressed(KeyCode::LShift)) && keys.pressed(KeyCode::S)
-    {
-        // clear saved
-        if let Some(entity) = saved_fen.text_entity {
-            commands.entity(entity).despawn_recursive();
-            saved_fen.text_entity = None;
-            saved_fen.saved = "".into();
+    if *mode == Mode::Play {
+        // the moves decide these mid game
+        return;
+    }
+
+    let shift = keys.pressed(KeyCode::LShift) || keys.pressed(KeyCode::RShift);
+
+    if keys.just_pressed(KeyCode::T) {
+        position.turn = position.turn.other();
+        // an en passant target only makes sense right after the double push
+        position.en_passant = None;
+    }
+
+    // the key matches the FEN letter, so shift (uppercase) is white
+    if keys.just_pressed(KeyCode::K) {
+        if shift {
+            position.castling.white_kingside = !position.castling.white_kingside;
+        } else {
+            position.castling.black_kingside = !position.castling.black_kingside;
+        }
+    }
+
+    if keys.just_pressed(KeyCode::Q) {
+        if shift {
+            position.castling.white_queenside = !position.castling.white_queenside;
+        } else {
+            position.castling.black_queenside = !position.castling.black_queenside;
         }
-    } else if keys.pressed(KeyCode::S) {
-        // save / overwite saved
-        if let Some(entity) = saved_fen.text_entity {
-            commands.entity(entity).despawn_recursive();
-            saved_fen.text_entity = None;
-            saved_fen.saved = "".into();
+    }
+}
+
+const SLOT_KEYS: [KeyCode; 9] = [
+    KeyCode::Key1,
+    KeyCode::Key2,
+    KeyCode::Key3,
+    KeyCode::Key4,
+    KeyCode::Key5,
+    KeyCode::Key6,
+    KeyCode::Key7,
+    KeyCode::Key8,
+    KeyCode::Key9,
+];
+
+pub fn toggle_save_position(mut saved_fen: ResMut<SavedFenState>, keys: Res<Input<KeyCode>>) {
+    let shift = keys.pressed(KeyCode::RShift) || keys.pressed(KeyCode::LShift);
+
+    if keys.just_pressed(KeyCode::S) {
+        if shift {
+            // drop the most recently saved position
+            saved_fen.saved.pop();
+        } else {
+            let fen = saved_fen.curr.clone();
+            saved_fen.save(fen);
         }
+    }
 
-        let display_text = format!("saved: {}", saved_fen.curr.clone());
-        let entity: Entity = commands
-            .spawn_bundle(ButtonBundle {
-                style: Style {
-                    size: Size::new(Val::Px(400.0), Val::Px(40.0)),
-                    position_type: PositionType::Absolute,
-                    justify_content: JustifyContent::FlexStart,
-                    align_items: AlignItems::FlexStart,
-                    position: UiRect {
-                        left: Val::Px(30.0),
-                        bottom: Val::Px(65.0),
-                        ..default()
-                    },
-                    ..default()
-                },
-                color: Color::hex(clear_color_hex_string)
-                    .unwrap_or_else(|_| {
-                        panic!("couldn't make hex color from {}", clear_color_hex_string)
-                    })
-                    .into(),
-                ..Default::default()
-            })
-            .insert(FenElement)
-            .with_children(|parent| {
-                parent.spawn_bundle(TextBundle {
-                    text: Text {
-                        sections: vec![TextSection {
-                            value: display_text.clone(),
-                            style: TextStyle {
-                                font: fen_assets.regular_font_handle.clone(),
-                                font_size: 14.0,
-                                color: Color::hex(text_color_hex_string).unwrap_or_else(|_| {
-                                    panic!("couldn't make hex color from {}", text_color_hex_string)
-                                }),
-                            },
-                        }],
-                        alignment: TextAlignment {
-                            vertical: VerticalAlign::Center,
-                            horizontal: HorizontalAlign::Center,
-                        },
+    if shift {
+        for (slot, key) in SLOT_KEYS.iter().enumerate() {
+            if keys.just_pressed(*key) {
+                let fen = saved_fen.curr.clone();
+                saved_fen.save_to(slot, fen);
+            }
+        }
+    }
+}
+
+/// the text in the editable "current:" field
+#[derive(Component, Default)]
+pub struct FenInput {
+    pub text: String,
+    pub editing: bool,
+    pub copied: bool,
+}
+
+enum SlotAction {
+    Load(usize),
+    Overwrite(usize),
+    Delete(usize),
+}
+
+pub fn position_panel(
+    mut egui_context: ResMut<EguiContext>,
+    layout: Res<BoardLayout>,
+    mut fen_input: ResMut<FenInput>,
+    mut saved_fen: ResMut<SavedFenState>,
+    position_problems: Res<PositionProblems>,
+    mut fen_error: ResMut<FenErrorState>,
+    mut load_events: EventWriter<LoadFen>,
+    mut mode: ResMut<Mode>,
+    mut promotion: ResMut<Promotion>,
+    selected_query: Query<&Piece, WithSelectedPiece>,
+) {
+    let text_color = egui::Color32::from_rgb(0x26, 0x26, 0x26);
+    let copied_color = egui::Color32::from_rgb(0xa1, 0xa1, 0xa1);
+    let error_color = egui::Color32::from_rgb(0x7a, 0x1f, 0x1f);
+    let warning_color = egui::Color32::from_rgb(0x6b, 0x4a, 0x00);
+
+    if !fen_input.editing {
+        // follow the board whenever nobody's typing
+        fen_input.text = saved_fen.curr.clone();
+    }
+
+    let fen_color = if fen_input.copied {
+        copied_color
+    } else {
+        text_color
+    };
+
+    let error_index = error_index(&fen_error, &fen_input.text);
+    let mut layouter = |ui: &egui::Ui, text: &str, _wrap_width: f32| {
+        let plain = egui::TextFormat {
+            font_id: egui::FontId::monospace(14.0),
+            color: fen_color,
+            ..default()
+        };
+        let mut job = egui::text::LayoutJob::default();
+        match error_index.and_then(|index| text.char_indices().nth(index)) {
+            Some((byte, c)) => {
+                let end = byte + c.len_utf8();
+                job.append(&text[..byte], 0.0, plain.clone());
+                job.append(
+                    &text[byte..end],
+                    0.0,
+                    egui::TextFormat {
+                        color: egui::Color32::WHITE,
+                        background: error_color,
+                        ..plain.clone()
                     },
-                    ..Default::default()
+                );
+                job.append(&text[end..], 0.0, plain);
+            }
+            None => job.append(text, 0.0, plain),
+        }
+        ui.fonts().layout_job(job)
+    };
+
+    let mut submitted = false;
+    let mut editing = fen_input.editing;
+    let mut slot_action = None;
+
+    let offset = layout.ui_points(30.0, 20.0);
+    egui::Area::new("position")
+        .anchor(egui::Align2::LEFT_BOTTOM, [offset.x, -offset.y])
+        .show(egui_context.ctx_mut(), |ui| {
+            ui.label(
+                egui::RichText::new("position\n--------------------")
+                    .monospace()
+                    .color(text_color),
+            );
+            mode_controls(
+                ui,
+                &mut mode,
+                &mut promotion,
+                !selected_query.is_empty(),
+                text_color,
+            );
+            ui.horizontal(|ui| {
+                ui.label(
+                    egui::RichText::new("current:")
+                        .monospace()
+                        .color(text_color),
+                );
+                let response = ui.add(
+                    egui::TextEdit::singleline(&mut fen_input.text)
+                        // up to the side panel column, leaving room for the label
+                        .desired_width(layout.panel_left() - 140.0)
+                        .layouter(&mut layouter),
+                );
+                submitted = response.lost_focus() && ui.input().key_pressed(egui::Key::Enter);
+                editing = still_editing(response.has_focus(), submitted, &fen_input.text);
+                if submitted && editing {
+                    // stay in the field until there's something that parses
+                    response.request_focus();
+                }
+            });
+            if let Some(error) = &fen_error.error {
+                ui.label(
+                    egui::RichText::new(error.to_string())
+                        .monospace()
+                        .color(error_color),
+                );
+            }
+            for problem in &position_problems.problems {
+                ui.label(
+                    egui::RichText::new(format!("! {}", problem))
+                        .monospace()
+                        .color(warning_color),
+                );
+            }
+            if !saved_fen.saved.is_empty() {
+                ui.horizontal_wrapped(|ui| {
+                    ui.label(egui::RichText::new("saved:").monospace().color(text_color));
+                    for (slot, saved) in saved_fen.saved.iter_mut().enumerate() {
+                        let label = if slot < SLOT_KEYS.len() {
+                            format!("{}: {}", slot + 1, saved.name)
+                        } else {
+                            saved.name.clone()
+                        };
+                        let response = ui.button(label).on_hover_text(saved.fen.as_str());
+                        if response.clicked() {
+                            slot_action = Some(SlotAction::Load(slot));
+                        }
+                        response.context_menu(|ui| {
+                            ui.text_edit_singleline(&mut saved.name);
+                            if ui.button("overwrite with current").clicked() {
+                                slot_action = Some(SlotAction::Overwrite(slot));
+                                ui.close_menu();
+                            }
+                            if ui.button("delete").clicked() {
+                                slot_action = Some(SlotAction::Delete(slot));
+                                ui.close_menu();
+                            }
+                        });
+                    }
                 });
-            })
-            .id();
-        saved_fen.text_entity = Some(entity);
-        saved_fen.saved = saved_fen.curr.clone();
+            }
+        });
+
+    match slot_action {
+        Some(SlotAction::Load(slot)) => {
+            load_events.send(LoadFen(saved_fen.saved[slot].fen.clone()))
+        }
+        Some(SlotAction::Overwrite(slot)) => {
+            let fen = saved_fen.curr.clone();
+            saved_fen.save_to(slot, fen);
+        }
+        Some(SlotAction::Delete(slot)) => {
+            saved_fen.saved.remove(slot);
+        }
+        None => {}
+    }
+
+    if submitted {
+        load_events.send(LoadFen(fen_input.text.clone()));
+    } else if fen_input.editing && !editing && fen_error.input == fen_input.text.trim() {
+        // gave up on the edit, so the error about it goes too
+        *fen_error = FenErrorState::default();
     }
+
+    fen_input.editing = editing;
 }
 
-pub fn spawn(mut commands: Commands, fen_assets: Res<TextAssets>) {
-    let clear_color_hex_string = "69696b";
-    let txt_val = "FEN NOTATION";
-    let positions_text = indoc! {"
-        position
-        --------------------
-        current: "};
-
-    commands
-        .spawn_bundle(NodeBundle {
-            style: Style {
-                size: Size::new(Val::Px(400.0), Val::Px(40.0)),
-                position_type: PositionType::Absolute,
-                justify_content: JustifyContent::FlexStart,
-                align_items: AlignItems::FlexStart,
-                position: UiRect {
-                    left: Val::Px(30.0),
-                    bottom: Val::Px(80.0),
-                    ..default()
-                },
-                ..default()
-            },
-            color: Color::hex(clear_color_hex_string)
-                .unwrap_or_else(|_| {
-                    panic!("couldn't make hex color from {}", clear_color_hex_string)
-                })
-                .into(),
-            ..Default::default()
+// the character in `text` to mark, if the error is about what's in the field
+fn error_index(fen_error: &FenErrorState, text: &str) -> Option<usize> {
+    fen_error
+        .error
+        .as_ref()
+        .filter(|_| fen_error.input == text.trim())
+        .map(|error| {
+            // the error counts characters from the trimmed FEN, so the leading space is
+            // counted in characters too, not bytes
+            let leading = text.chars().count() - text.trim_start().chars().count();
+            error.index() + leading
         })
-        .insert(FenElement)
-        .with_children(|parent| {
-            parent
-                .spawn_bundle(TextBundle {
-                    text: Text {
-                        sections: vec![
-                            TextSection {
-                                value: positions_text.to_string(),
-                                style: TextStyle {
-                                    font: fen_assets.regular_font_handle.clone(),
-                                    font_size: 14.0,
-                                    color: Color::rgb(0.15, 0.15, 0.15),
-                                },
-                            },
-                            TextSection {
-                                value: txt_val.to_string(),
-                                style: TextStyle {
-                                    font: fen_assets.regular_font_handle.clone(),
-                                    font_size: 14.0,
-                                    color: Color::rgb(0.15, 0.15, 0.15),
-                                },
-                            },
-                        ],
-                        alignment: TextAlignment {
-                            vertical: VerticalAlign::Center,
-                            horizontal: HorizontalAlign::Left,
-                        },
-                    },
-                    ..default()
-                })
-                .insert(FenText);
-        });
+}
+
+// whether the field's still being typed in. enter takes the focus away for the frame
+// even when the FEN's rejected, and the focus only comes back on the next one, so
+// without this the board's FEN would replace the text and its error
+fn still_editing(has_focus: bool, submitted: bool, text: &str) -> bool {
+    has_focus || (submitted && Position::from_fen(text.trim()).is_err())
+}
+
+/// cmd on macOS, ctrl everywhere else, where the OS keeps the windows key for itself
+pub fn command_pressed(keys: &Input<KeyCode>) -> bool {
+    keys.pressed(KeyCode::LControl)
+        || keys.pressed(KeyCode::RControl)
+        || keys.pressed(KeyCode::LWin)
+        || keys.pressed(KeyCode::RWin)
 }
 
 pub fn copy_to_clipboard(
     mut clipboard: ResMut<bevy_egui::EguiClipboard>,
     keys: Res<Input<KeyCode>>,
-    mut fen_text_query: Query<&mut Text, WithFenText>,
+    saved_fen: Res<SavedFenState>,
+    mut fen_input: ResMut<FenInput>,
 ) {
-    let clicked_color_hex_string = "a1a1a1";
-    if let Ok(mut fen_text) = fen_text_query.get_single_mut() {
-        if keys.pressed(KeyCode::LWin) && keys.pressed(KeyCode::C) {
-            clipboard.set_contents(&fen_text.sections[1].value);
-            fen_text.sections[1].style.color =
-                Color::hex(clicked_color_hex_string).unwrap_or_else(|_| {
-                    panic!("couldn't make hex color from {}", clicked_color_hex_string)
-                });
-        } else {
-            fen_text.sections[1].style.color = Color::rgb(0.15, 0.15, 0.15);
-        }
+    if command_pressed(&keys) && keys.pressed(KeyCode::C) {
+        clipboard.set_contents(&saved_fen.curr);
+        fen_input.copied = true;
+    } else {
+        fen_input.copied = false;
     }
 }
 
 pub fn populate_board_from_fen(
     saved_fen: Res<SavedFenState>,
-    board: Res<Board>,
-    piece_material_handles: Res<PieceMaterialHandles>,
-    mut commands: Commands,
-    mut meshes: ResMut<Assets<Mesh>>,
-    query: Query<(Entity, &Piece, With<PickableMesh>, WithActivePiece)>,
+    mut load_events: EventWriter<LoadFen>,
     keys: Res<Input<KeyCode>>,
 ) {
-    if !keys.pressed(KeyCode::R) {
+    if keys.pressed(KeyCode::RShift) || keys.pressed(KeyCode::LShift) {
+        // shift + number saves instead
         return;
     }
 
-    if saved_fen.text_entity.is_none() {
-        // there's no saved position to restore
-        return;
+    if keys.just_pressed(KeyCode::R) {
+        if let Some(saved) = saved_fen.saved.last() {
+            load_events.send(LoadFen(saved.fen.clone()));
+        }
     }
 
-    // clear board
-    for (entity, _piece, _, _) in query.iter() {
-        commands.entity(entity).despawn_recursive();
+    for (slot, key) in SLOT_KEYS.iter().enumerate() {
+        if keys.just_pressed(*key) {
+            if let Some(saved) = saved_fen.saved.get(slot) {
+                load_events.send(LoadFen(saved.fen.clone()));
+            }
+        }
     }
+}
 
-    for (rank, fen) in saved_fen.saved.split('/').enumerate() {
-        populate_for_fen_rank(
-            8 - rank,
-            fen,
-            &board,
-            &piece_material_handles,
-            &mut commands,
-            &mut meshes,
-        );
+pub fn paste_from_clipboard(
+    clipboard: Res<bevy_egui::EguiClipboard>,
+    mut load_events: EventWriter<LoadFen>,
+    keys: Res<Input<KeyCode>>,
+) {
+    if command_pressed(&keys) && keys.just_pressed(KeyCode::V) {
+        load_events.send(LoadFen(clipboard.get_contents().unwrap_or_default()));
     }
 }
 
-pub fn populate_for_fen_rank(
-    rank: usize,
-    fen: &str,
-    board: &Res<Board>,
-    piece_material_handles: &Res<PieceMaterialHandles>,
-    commands: &mut Commands,
-    meshes: &mut ResMut<Assets<Mesh>>,
-) {
-    let num_to_file = |i| match i {
-        1 => 'a',
-        2 => 'b',
-        3 => 'c',
-        4 => 'd',
-        5 => 'e',
-        6 => 'f',
-        7 => 'g',
-        _ => 'h',
-    };
+/// replace everything on the board with a FEN, or say why it can't be
+pub struct LoadFen(pub String);
 
-    let mut file_num = 1;
-
-    for c in fen.chars() {
-        if let Some(p) = piece_for_fen_char(c) {
-            let pos = format!("{}{}", num_to_file(file_num), rank);
-            let material_handle = p.material_handle((**piece_material_handles).clone());
-            place_piece(
-                &pos,
-                p,
-                ActivePiece,
-                &material_handle,
-                board,
-                commands,
-                meshes,
-            );
-            file_num += 1;
-        } else if let Some(d) = c.to_digit(10) {
-            if d < 9 && d > 0 {
-                file_num += d;
+pub fn load_fen(
+    mut load_events: EventReader<LoadFen>,
+    mut position: ResMut<Position>,
+    mut fen_error: ResMut<FenErrorState>,
+    layout: Res<BoardLayout>,
+    piece_material_handles: Res<PieceMaterialHandles>,
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    query: Query<(Entity, &Piece, With<PickableMesh>, WithActivePiece)>,
+) {
+    // only the last one would be left standing anyway
+    if let Some(LoadFen(fen)) = load_events.iter().last() {
+        let fen = fen.trim();
+        match Position::from_fen(fen) {
+            Ok(parsed) => {
+                replace_board(
+                    parsed,
+                    &query,
+                    &mut position,
+                    &layout,
+                    &piece_material_handles,
+                    &mut commands,
+                    &mut meshes,
+                );
+                *fen_error = FenErrorState::default();
             }
+            Err(error) => fen_error.set(fen, error),
         }
     }
 }
 
-pub fn piece_for_fen_char(piece: char) -> Option<Side> {
-    match piece {
-        'q' => Some(Side::Black(Kind::Queen)),
-        'k' => Some(Side::Black(Kind::King)),
-        'b' => Some(Side::Black(Kind::Bishop)),
-        'n' => Some(Side::Black(Kind::Knight)),
-        'r' => Some(Side::Black(Kind::Rook)),
-        'p' => Some(Side::Black(Kind::Pawn)),
-        'Q' => Some(Side::White(Kind::Queen)),
-        'K' => Some(Side::White(Kind::King)),
-        'B' => Some(Side::White(Kind::Bishop)),
-        'N' => Some(Side::White(Kind::Knight)),
-        'R' => Some(Side::White(Kind::Rook)),
-        'P' => Some(Side::White(Kind::Pawn)),
-        _ => None,
-    }
-}
-pub fn generate_fen(
-    mut text_query: Query<&mut Text, WithFenText>,
-    mut saved_fen_state: ResMut<SavedFenState>,
-    active_pieces_query: Query<(&Piece, &Transform, WithActivePiece)>,
+/// swap every piece on the board for the ones in `parsed`
+pub fn replace_board(
+    parsed: Position,
+    query: &Query<(Entity, &Piece, With<PickableMesh>, WithActivePiece)>,
+    position: &mut ResMut<Position>,
+    layout: &Res<BoardLayout>,
+    piece_material_handles: &Res<PieceMaterialHandles>,
+    commands: &mut Commands,
+    meshes: &mut ResMut<Assets<Mesh>>,
 ) {
-    let mut occupied_positions: Vec<(Piece, Square)> = vec![];
-    for (piece, transform, _) in active_pieces_query.iter() {
-        if let Some(square) = get_square(transform.translation.x, transform.translation.y) {
-            occupied_positions.push((piece.clone(), square));
-        }
+    // clear board
+    for (entity, _piece, _, _) in query.iter() {
+        commands.entity(entity).despawn_recursive();
     }
 
-    let fen = &format!(
-        "{}/{}/{}/{}/{}/{}/{}/{}",
-        get_fen_for_rank(&Rank::Eight, occupied_positions.clone()),
-        get_fen_for_rank(&Rank::Seven, occupied_positions.clone()),
-        get_fen_for_rank(&Rank::Six, occupied_positions.clone()),
-        get_fen_for_rank(&Rank::Five, occupied_positions.clone()),
-        get_fen_for_rank(&Rank::Four, occupied_positions.clone()),
-        get_fen_for_rank(&Rank::Three, occupied_positions.clone()),
-        get_fen_for_rank(&Rank::Two, occupied_positions.clone()),
-        get_fen_for_rank(&Rank::One, occupied_positions.clone()),
-    );
-
-    saved_fen_state.curr = fen.clone();
-
-    if let Ok(mut text) = text_query.get_single_mut() {
-        text.sections[1].value = fen.into();
-    }
+    populate_board(&parsed, layout, piece_material_handles, commands, meshes);
+    **position = parsed;
 }
 
-fn fen_str(p: Piece, curr_empty_count: usize) -> String {
-    let mut res = String::new();
-    if curr_empty_count != 0 {
-        let _ = write!(res, "{}", curr_empty_count);
+pub fn populate_board(
+    position: &Position,
+    layout: &Res<BoardLayout>,
+    piece_material_handles: &Res<PieceMaterialHandles>,
+    commands: &mut Commands,
+    meshes: &mut ResMut<Assets<Mesh>>,
+) {
+    for (square, side) in position.pieces() {
+        place_piece(
+            layout.square_center(&square),
+            side.clone(),
+            ActivePiece,
+            &piece_material_handles.for_side(side),
+            layout,
+            commands,
+            meshes,
+        );
     }
-    res += p.def.fen_str();
-    res
 }
 
-fn piece_on_given_square(
-    pieces_on_file: &[(Piece, Square)],
-    given_square: Square,
-) -> Option<Piece> {
-    if let Some((piece, _)) = pieces_on_file.iter().find(|e| e.1 == given_square) {
-        Some(piece.clone())
-    } else {
-        None
-    }
+/// the last FEN that was rejected and why, shown under the current position
+#[derive(Component, Default)]
+pub struct FenErrorState {
+    pub input: String,
+    pub error: Option<FenError>,
 }
 
-fn get_fen_for_rank(rank: &Rank, occupied_positions: Vec<(Piece, Square)>) -> String {
-    let mut fen_for_rank = String::new();
-
-    let pieces_on_rank = occupied_positions
-        .into_iter()
-        .filter(|(_, square)| square.rank == *rank)
-        .collect::<Vec<(Piece, Square)>>();
-
-    let mut curr_empty_count: usize = 0;
-
-    if let Some(p) = piece_on_given_square(
-        &pieces_on_rank,
-        Square {
-            file: File::A,
-            rank: rank.clone(),
-        },
-    ) {
-        fen_for_rank += &fen_str(p, curr_empty_count);
-        curr_empty_count = 0;
-    } else {
-        curr_empty_count += 1;
-    }
-
-    if let Some(p) = piece_on_given_square(
-        &pieces_on_rank,
-        Square {
-            file: File::B,
-            rank: rank.clone(),
-        },
-    ) {
-        fen_for_rank += &fen_str(p, curr_empty_count);
-        curr_empty_count = 0;
-    } else {
-        curr_empty_count += 1;
-    }
-
-    if let Some(p) = piece_on_given_square(
-        &pieces_on_rank,
-        Square {
-            file: File::C,
-            rank: rank.clone(),
-        },
-    ) {
-        fen_for_rank += &fen_str(p, curr_empty_count);
-        curr_empty_count = 0;
-    } else {
-        curr_empty_count += 1;
-    }
-
-    if let Some(p) = piece_on_given_square(
-        &pieces_on_rank,
-        Square {
-            file: File::D,
-            rank: rank.clone(),
-        },
-    ) {
-        fen_for_rank += &fen_str(p, curr_empty_count);
-        curr_empty_count = 0;
-    } else {
-        curr_empty_count += 1;
+impl FenErrorState {
+    pub fn set(&mut self, input: &str, error: FenError) {
+        self.input = input.into();
+        self.error = Some(error);
     }
+}
 
-    if let Some(p) = piece_on_given_square(
-        &pieces_on_rank,
-        Square {
-            file: File::E,
-            rank: rank.clone(),
-        },
-    ) {
-        fen_for_rank += &fen_str(p, curr_empty_count);
-        curr_empty_count = 0;
-    } else {
-        curr_empty_count += 1;
-    }
+/// keeps the `Position` resource in step with the pieces on the board while setting up,
+/// and the current FEN in step with that. while playing it's the other way around
+pub fn generate_fen(
+    mut saved_fen_state: ResMut<SavedFenState>,
+    mut position: ResMut<Position>,
+    layout: Res<BoardLayout>,
+    mode: Res<Mode>,
+    active_pieces_query: Query<(&Piece, &Transform, WithActivePiece)>,
+) {
+    if *mode == Mode::Setup {
+        let mut on_board = position.clone();
+        on_board.clear();
+        for (piece, transform, _) in active_pieces_query.iter() {
+            if let Some(square) = layout.square_at(transform.translation.truncate()) {
+                on_board.put(square, piece.def.clone());
+            }
+        }
 
-    if let Some(p) = piece_on_given_square(
-        &pieces_on_rank,
-        Square {
-            file: File::F,
-            rank: rank.clone(),
-        },
-    ) {
-        fen_for_rank += &fen_str(p, curr_empty_count);
-        curr_empty_count = 0;
-    } else {
-        curr_empty_count += 1;
+        // only touch the resource when a piece has moved so change detection means something
+        if on_board != *position {
+            *position = on_board;
+        }
     }
 
-    if let Some(p) = piece_on_given_square(
-        &pieces_on_rank,
-        Square {
-            file: File::G,
-            rank: rank.clone(),
-        },
-    ) {
-        fen_for_rank += &fen_str(p, curr_empty_count);
-        curr_empty_count = 0;
-    } else {
-        curr_empty_count += 1;
+    let fen = position.to_fen();
+    if fen != saved_fen_state.curr {
+        saved_fen_state.curr = fen;
     }
+}
 
-    if let Some(p) = piece_on_given_square(
-        &pieces_on_rank,
-        Square {
-            file: File::H,
-            rank: rank.clone(),
-        },
-    ) {
-        fen_for_rank += &fen_str(p, curr_empty_count);
-        curr_empty_count = 0;
-    } else {
-        curr_empty_count += 1;
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rejected_submit_keeps_the_text_and_its_error() {
+        let typed = "  rnbqkbnr/ppppxppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+        // enter's taken the focus, but the field's still the one being edited
+        assert!(still_editing(false, true, typed));
+
+        // so the text stays as typed when the error comes back, and its character's marked
+        let mut fen_error = FenErrorState::default();
+        fen_error.set(typed.trim(), Position::from_fen(typed.trim()).unwrap_err());
+        let index = error_index(&fen_error, typed).unwrap();
+        assert_eq!(typed.chars().nth(index), Some('x'));
+
+        // the leading space is counted in characters
+        let typed = "\u{3000}rnbqkbnr/ppppxppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+        fen_error.set(typed.trim(), Position::from_fen(typed.trim()).unwrap_err());
+        let index = error_index(&fen_error, typed).unwrap();
+        assert_eq!(typed.chars().nth(index), Some('x'));
+
+        // nothing's marked once the text's something else
+        assert_eq!(error_index(&fen_error, "8/8/8/8/8/8/8/8 w - - 0 1"), None);
     }
 
-    if curr_empty_count != 0 {
-        let _ = &write!(fen_for_rank, "{}", curr_empty_count);
+    #[test]
+    fn accepted_submit_stops_editing() {
+        let fen = Position::starting().to_fen();
+        assert!(!still_editing(false, true, &fen));
+        // clicking away from a bad FEN gives up on it
+        assert!(!still_editing(false, false, "not a fen"));
+        assert!(still_editing(true, false, "not a fen"));
     }
-
-    fen_for_rank
 }
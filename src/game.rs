@@ -0,0 +1,319 @@
+use crate::{moves::Move, pgn::Tag, position::Position};
+
+/// a move in a game, what's been said about it and the moves that can follow it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Node {
+    pub mv: Move,
+    pub san: String,
+    // numeric annotation glyphs, $1 is "!", $2 is "?" and so on
+    pub nags: Vec<u8>,
+    // a comment ahead of the move, only kept for the first move of a line
+    pub before: Option<String>,
+    pub comment: Option<String>,
+    // the main line first, then the variations
+    pub children: Vec<Node>,
+}
+
+impl Node {
+    pub fn new(mv: Move, san: String) -> Node {
+        Node {
+            mv,
+            san,
+            nags: vec![],
+            before: None,
+            comment: None,
+            children: vec![],
+        }
+    }
+}
+
+/// a game as a tree of moves from `start`. a node is found by its path, the index of
+/// the move chosen at each step, so `[]` is the start and `[0, 0, 1]` is the first
+/// variation on the main line's third move
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Game {
+    pub tags: Vec<Tag>,
+    pub start: Position,
+    // a comment that comes before anything's been played
+    pub comment: Option<String>,
+    // the first moves, the main line first
+    pub children: Vec<Node>,
+}
+
+impl Game {
+    pub fn new(start: Position) -> Game {
+        Game {
+            start,
+            ..Game::default()
+        }
+    }
+
+    pub fn node(&self, path: &[usize]) -> Option<&Node> {
+        let (last, parent) = path.split_last()?;
+        self.children(parent)?.get(*last)
+    }
+
+    pub fn node_mut(&mut self, path: &[usize]) -> Option<&mut Node> {
+        let (last, parent) = path.split_last()?;
+        self.children_mut(parent)?.get_mut(*last)
+    }
+
+    /// the moves that can follow the one at `path`
+    pub fn children(&self, path: &[usize]) -> Option<&Vec<Node>> {
+        match path.split_last() {
+            Some(_) => self.node(path).map(|node| &node.children),
+            None => Some(&self.children),
+        }
+    }
+
+    pub fn children_mut(&mut self, path: &[usize]) -> Option<&mut Vec<Node>> {
+        match path.split_last() {
+            Some(_) => self.node_mut(path).map(|node| &mut node.children),
+            None => Some(&mut self.children),
+        }
+    }
+
+    /// the position once the moves along `path` are played
+    pub fn position_at(&self, path: &[usize]) -> Option<Position> {
+        let mut position = self.start.clone();
+        let mut children = &self.children;
+        for index in path {
+            let node = children.get(*index)?;
+            position.play(&node.mv);
+            children = &node.children;
+        }
+        Some(position)
+    }
+
+    /// the path that follows the first move at every step, from `path` to the end
+    pub fn mainline_from(&self, path: &[usize]) -> Vec<usize> {
+        let mut path = path.to_vec();
+        while self
+            .children(&path)
+            .is_some_and(|children| !children.is_empty())
+        {
+            path.push(0);
+        }
+        path
+    }
+
+    /// the path to `mv` played after `path`, adding it after any moves already there
+    /// unless it's one of them
+    pub fn add_move(&mut self, path: &[usize], mv: &Move) -> Option<Vec<usize>> {
+        let san = self.position_at(path)?.san(mv);
+        let children = self.children_mut(path)?;
+
+        let index = match children.iter().position(|node| node.mv == *mv) {
+            Some(index) => index,
+            None => {
+                children.push(Node::new(mv.clone(), san));
+                children.len() - 1
+            }
+        };
+
+        let mut path = path.to_vec();
+        path.push(index);
+        Some(path)
+    }
+
+    /// the moves played along `path`
+    pub fn moves_along(&self, path: &[usize]) -> Vec<Move> {
+        (1..=path.len())
+            .filter_map(|length| self.node(&path[..length]))
+            .map(|node| node.mv.clone())
+            .collect()
+    }
+
+    /// the path that plays `moves`, or as many of them as are still in the game
+    pub fn follow(&self, moves: &[Move]) -> Vec<usize> {
+        let mut path = vec![];
+        for mv in moves {
+            match self
+                .children(&path)
+                .and_then(|children| children.iter().position(|node| node.mv == *mv))
+            {
+                Some(index) => path.push(index),
+                None => break,
+            }
+        }
+        path
+    }
+
+    /// make the line through `path` the main line at every point it branches off
+    pub fn promote(&mut self, path: &[usize]) {
+        if self.node(path).is_none() {
+            return;
+        }
+
+        let mut promoted = vec![];
+        for index in path {
+            if let Some(children) = self.children_mut(&promoted) {
+                let node = children.remove(*index);
+                children.insert(0, node);
+            }
+            promoted.push(0);
+        }
+    }
+
+    /// take out the variation the move at `path` is in, from where it branches off,
+    /// along with everything that follows. a main line move goes with everything after it
+    pub fn remove_variation(&mut self, path: &[usize]) {
+        let depth = match path.iter().rposition(|index| *index != 0) {
+            Some(depth) => depth,
+            None if !path.is_empty() => path.len() - 1,
+            None => return,
+        };
+        if let Some(children) = self.children_mut(&path[..depth]) {
+            if path[depth] < children.len() {
+                children.remove(path[depth]);
+            }
+        }
+    }
+
+    /// the value of the tag called `name`
+    pub fn tag(&self, name: &str) -> Option<&str> {
+        self.tags
+            .iter()
+            .find(|tag| tag.name == name)
+            .map(|tag| tag.value.as_str())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // `sans` played one after another from `position`
+    fn moves(position: &Position, sans: &[&str]) -> Vec<Move> {
+        let mut position = position.clone();
+        sans.iter()
+            .map(|san| {
+                let mv = position.parse_san(san).unwrap();
+                position.play(&mv);
+                mv
+            })
+            .collect()
+    }
+
+    // 1. e4 e5 (1... c5 2. Nf3 (2. Nc3)) 2. Nf3
+    fn sicilian_aside() -> Game {
+        let mut game = Game::new(Position::starting());
+        for (from, sans) in [
+            (vec![], ["e4", "e5", "Nf3"].as_slice()),
+            (vec![0], &["c5", "Nf3"]),
+            (vec![0, 1], &["Nc3"]),
+        ] {
+            let mut path = from;
+            for mv in moves(&game.position_at(&path).unwrap(), sans) {
+                path = game.add_move(&path, &mv).unwrap();
+            }
+        }
+        game
+    }
+
+    fn sans(game: &Game, path: &[usize]) -> Vec<String> {
+        (1..=path.len())
+            .map(|length| game.node(&path[..length]).unwrap().san.clone())
+            .collect()
+    }
+
+    fn replies(game: &Game, path: &[usize]) -> Vec<String> {
+        game.children(path)
+            .unwrap()
+            .iter()
+            .map(|node| node.san.clone())
+            .collect()
+    }
+
+    #[test]
+    fn add_move() {
+        let mut game = Game::new(Position::starting());
+        let start = game.start.clone();
+        let e4 = moves(&start, &["e4"]).remove(0);
+        let d4 = moves(&start, &["d4"]).remove(0);
+
+        assert_eq!(game.add_move(&[], &e4), Some(vec![0]));
+        // the same move again is followed rather than added twice
+        assert_eq!(game.add_move(&[], &e4), Some(vec![0]));
+        // a different one goes after it, as a variation
+        assert_eq!(game.add_move(&[], &d4), Some(vec![1]));
+        assert_eq!(game.children.len(), 2);
+        assert_eq!(game.node(&[1]).unwrap().san, "d4");
+        // there's nothing at [2] to play after
+        assert_eq!(game.add_move(&[2], &e4), None);
+
+        let game = sicilian_aside();
+        assert_eq!(sans(&game, &[0, 0, 0]), ["e4", "e5", "Nf3"]);
+        assert_eq!(sans(&game, &[0, 1, 0]), ["e4", "c5", "Nf3"]);
+        assert_eq!(sans(&game, &[0, 1, 1]), ["e4", "c5", "Nc3"]);
+        assert_eq!(
+            game.position_at(&[0, 1]).unwrap().to_fen(),
+            "rnbqkbnr/pp1ppppp/8/2p5/4P3/8/PPPP1PPP/RNBQKBNR w KQkq c6 0 2"
+        );
+    }
+
+    #[test]
+    fn mainline_from() {
+        let game = sicilian_aside();
+        assert_eq!(game.mainline_from(&[]), [0, 0, 0]);
+        assert_eq!(game.mainline_from(&[0, 1]), [0, 1, 0]);
+        assert_eq!(game.mainline_from(&[0, 1, 1]), [0, 1, 1]);
+        assert_eq!(Game::default().mainline_from(&[]), Vec::<usize>::new());
+    }
+
+    #[test]
+    fn follow() {
+        let game = sicilian_aside();
+        let start = &game.start;
+        assert_eq!(game.follow(&moves(start, &["e4", "c5", "Nc3"])), [0, 1, 1]);
+        assert_eq!(game.follow(&moves(start, &["e4", "e5"])), [0, 0]);
+        // as far as the game goes
+        assert_eq!(game.follow(&moves(start, &["e4", "e5", "Nc3"])), [0, 0]);
+        assert_eq!(game.follow(&moves(start, &["d4"])), Vec::<usize>::new());
+        assert_eq!(game.follow(&game.moves_along(&[0, 1, 0])), [0, 1, 0]);
+    }
+
+    #[test]
+    fn promote() {
+        let mut game = sicilian_aside();
+        game.promote(&[0, 1, 1]);
+        assert_eq!(sans(&game, &game.mainline_from(&[])), ["e4", "c5", "Nc3"]);
+        assert_eq!(replies(&game, &[0]), ["c5", "e5"]);
+        assert_eq!(replies(&game, &[0, 0]), ["Nc3", "Nf3"]);
+        // what was the main line is a variation on it now
+        assert_eq!(sans(&game, &[0, 1, 0]), ["e4", "e5", "Nf3"]);
+
+        // promoting the main line or a move that isn't there changes nothing
+        let before = game.clone();
+        game.promote(&[0, 0, 0]);
+        game.promote(&[0, 5]);
+        assert_eq!(game, before);
+    }
+
+    #[test]
+    fn remove_variation() {
+        // a move in a variation takes the whole variation with it
+        let mut game = sicilian_aside();
+        game.remove_variation(&[0, 1, 0]);
+        assert_eq!(replies(&game, &[0]), ["e5"]);
+        assert_eq!(sans(&game, &game.mainline_from(&[])), ["e4", "e5", "Nf3"]);
+
+        // a variation inside a variation only takes itself
+        let mut game = sicilian_aside();
+        game.remove_variation(&[0, 1, 1]);
+        assert_eq!(replies(&game, &[0]), ["e5", "c5"]);
+        assert_eq!(replies(&game, &[0, 1]), ["Nf3"]);
+
+        // a main line move goes with everything after it, variations included
+        let mut game = sicilian_aside();
+        game.remove_variation(&[0, 0]);
+        assert_eq!(replies(&game, &[0]), ["c5"]);
+        assert_eq!(sans(&game, &game.mainline_from(&[])), ["e4", "c5", "Nf3"]);
+
+        // nothing to remove at the start, or past the end
+        let mut game = sicilian_aside();
+        game.remove_variation(&[]);
+        game.remove_variation(&[0, 7]);
+        assert_eq!(game, sicilian_aside());
+    }
+}
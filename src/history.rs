@@ -0,0 +1,419 @@
+use crate::{
+    board::BoardLayout,
+    export::{export_controls, PgnExport},
+    fen::LoadFen,
+    import::{import_controls, PgnImport},
+};
+use bevy::prelude::*;
+use bevy_egui::{egui, EguiClipboard, EguiContext};
+use chessbored::{
+    game::{Game, Node},
+    moves::Move,
+    pgn::nag_glyph,
+    position::{Position, Turn},
+};
+
+/// the game being played or stepped through, and where the board is in it
+#[derive(Component, Debug, Clone, Default)]
+pub struct MoveHistory {
+    pub game: Game,
+    // the path to the move the board's at, empty for the start
+    pub current: Vec<usize>,
+}
+
+impl MoveHistory {
+    pub fn position(&self) -> Position {
+        self.game
+            .position_at(&self.current)
+            .unwrap_or_else(|| self.game.start.clone())
+    }
+
+    /// play `mv` from the current position. a move that's already been played from here
+    /// is followed again, and a different one starts a variation. a board that's been
+    /// taken back to an earlier move carries on from there, one that's been set up or
+    /// loaded since starts a new game
+    pub fn play(&mut self, on_board: &Position, mv: &Move) {
+        let fen = on_board.to_fen();
+        let earlier = (0..=self.current.len()).rev().find(|length| {
+            self.game
+                .position_at(&self.current[..*length])
+                .is_some_and(|position| position.to_fen() == fen)
+        });
+        match earlier {
+            Some(length) => self.current.truncate(length),
+            None => {
+                *self = MoveHistory {
+                    game: Game::new(on_board.clone()),
+                    current: vec![],
+                }
+            }
+        }
+
+        if let Some(path) = self.game.add_move(&self.current, mv) {
+            self.current = path;
+        }
+    }
+
+    /// change the shape of the game, keeping the board on the same move if it's still
+    /// there or on the last one before it that is. says whether the board has to move
+    pub fn edit(&mut self, change: impl FnOnce(&mut Game)) -> bool {
+        let moves = self.game.moves_along(&self.current);
+        change(&mut self.game);
+        self.current = self.game.follow(&moves);
+        self.current.len() != moves.len()
+    }
+
+    /// start stepping through `game` from its first position
+    pub fn load(&mut self, game: Game) {
+        *self = MoveHistory {
+            game,
+            current: vec![],
+        };
+    }
+
+    /// "12." ahead of white's moves, and "12..." when black's move is the first shown,
+    /// for the move `ply` half moves in
+    fn move_number(&self, ply: usize, interrupted: bool) -> Option<String> {
+        let start = &self.game.start;
+        let black_first = (start.turn == Turn::Black) as usize;
+        let number = start.fullmove_number as usize + (ply + black_first) / 2;
+        match (ply + black_first) % 2 {
+            0 => Some(format!("{}.", number)),
+            _ if interrupted => Some(format!("{}...", number)),
+            _ => None,
+        }
+    }
+}
+
+// how much of the side panel column the move list can take before it scrolls
+const HISTORY_HEIGHT: f32 = 260.0;
+
+/// a move as it's shown in the list, with its annotations
+fn annotated(node: &Node) -> String {
+    let mut text = node.san.clone();
+    for nag in &node.nags {
+        match nag_glyph(*nag) {
+            Some(glyph) => text.push_str(glyph),
+            None => text.push_str(&format!(" ${}", nag)),
+        }
+    }
+    text
+}
+
+/// what's been asked of a move in the list
+enum MoveAction {
+    Jump(Vec<usize>),
+    Promote(Vec<usize>),
+    Delete(Vec<usize>),
+}
+
+/// draws the game into the move panel and keeps track of what's clicked
+struct MoveList<'a> {
+    history: &'a MoveHistory,
+    // where the board is, if it's on one of the moves
+    current: Option<Vec<usize>>,
+    scroll: bool,
+    text_color: egui::Color32,
+    comment_color: egui::Color32,
+    action: Option<MoveAction>,
+}
+
+impl<'a> MoveList<'a> {
+    /// the line that carries on after `parent`, with the alternatives to each move in
+    /// brackets after it, nested as deep as they go. the main line's variations get
+    /// rows of their own
+    fn line(&mut self, ui: &mut egui::Ui, parent: Vec<usize>, depth: usize, interrupted: bool) {
+        let history: &'a MoveHistory = self.history;
+        let mut path = parent;
+        let mut interrupted = interrupted;
+
+        while let Some(children) = history.game.children(&path).filter(|c| !c.is_empty()) {
+            path.push(0);
+            self.move_label(ui, &path, &children[0], interrupted);
+            interrupted = children[0].comment.is_some();
+
+            for (index, alternative) in children.iter().enumerate().skip(1) {
+                let mut branch = path.clone();
+                if let Some(last) = branch.last_mut() {
+                    *last = index;
+                }
+
+                if depth == 0 {
+                    ui.end_row();
+                }
+                ui.label(egui::RichText::new("(").color(self.text_color));
+                self.move_label(ui, &branch, alternative, true);
+                self.line(ui, branch, depth + 1, alternative.comment.is_some());
+                ui.label(egui::RichText::new(")").color(self.text_color));
+                if depth == 0 {
+                    ui.end_row();
+                }
+                interrupted = true;
+            }
+        }
+    }
+
+    fn move_label(&mut self, ui: &mut egui::Ui, path: &[usize], node: &Node, interrupted: bool) {
+        let mut interrupted = interrupted;
+        if let Some(text) = &node.before {
+            self.comment(ui, text);
+            interrupted = true;
+        }
+        if let Some(number) = self.history.move_number(path.len() - 1, interrupted) {
+            ui.label(
+                egui::RichText::new(number)
+                    .monospace()
+                    .color(self.text_color),
+            );
+        }
+
+        let at = self.current.as_deref() == Some(path);
+        let response = ui.selectable_label(at, egui::RichText::new(annotated(node)).monospace());
+        if at && self.scroll {
+            response.scroll_to_me(None);
+        }
+        if response.clicked() {
+            self.action = Some(MoveAction::Jump(path.to_vec()));
+        }
+
+        let in_variation = path.iter().any(|index| *index != 0);
+        response.context_menu(|ui| {
+            if in_variation && ui.button("promote to main line").clicked() {
+                self.action = Some(MoveAction::Promote(path.to_vec()));
+                ui.close_menu();
+            }
+            let delete = if in_variation {
+                "delete variation"
+            } else {
+                "delete from here"
+            };
+            if ui.button(delete).clicked() {
+                self.action = Some(MoveAction::Delete(path.to_vec()));
+                ui.close_menu();
+            }
+        });
+
+        if let Some(text) = &node.comment {
+            self.comment(ui, text);
+        }
+    }
+
+    fn comment(&self, ui: &mut egui::Ui, text: &str) {
+        ui.label(
+            egui::RichText::new(text)
+                .italics()
+                .color(self.comment_color),
+        );
+    }
+}
+
+pub fn history_panel(
+    mut egui_context: ResMut<EguiContext>,
+    layout: Res<BoardLayout>,
+    position: Res<Position>,
+    mut history: ResMut<MoveHistory>,
+    mut export: ResMut<PgnExport>,
+    mut import: ResMut<PgnImport>,
+    mut clipboard: ResMut<EguiClipboard>,
+    mut load_events: EventWriter<LoadFen>,
+) {
+    let text_color = egui::Color32::from_rgb(0x26, 0x26, 0x26);
+    let comment_color = egui::Color32::from_rgb(0x4a, 0x4a, 0x4a);
+
+    // nothing's marked once the board's been moved away from the history
+    let on_board = position.to_fen();
+    let current = (history.position().to_fen() == on_board).then(|| history.current.clone());
+
+    let mut action = None;
+    let mut imported = None;
+
+    // the margins are the same top and bottom, so this is also from the top
+    let offset = layout.ui_points(layout.panel_left() + 10.0, 20.0);
+    egui::Area::new("moves")
+        .anchor(egui::Align2::LEFT_TOP, [offset.x, offset.y])
+        .show(egui_context.ctx_mut(), |ui| {
+            ui.set_width(layout.panel_width - 20.0);
+            ui.label(
+                egui::RichText::new("moves\n--------------------")
+                    .monospace()
+                    .color(text_color),
+            );
+            egui::ScrollArea::vertical()
+                .max_height(HISTORY_HEIGHT)
+                .auto_shrink([false, true])
+                .show(ui, |ui| {
+                    ui.horizontal_wrapped(|ui| {
+                        let mut list = MoveList {
+                            history: &history,
+                            current,
+                            scroll: history.is_changed(),
+                            text_color,
+                            comment_color,
+                            action: None,
+                        };
+                        let at_start = list.current.as_ref().is_some_and(Vec::is_empty);
+                        if ui.selectable_label(at_start, "start").clicked() {
+                            list.action = Some(MoveAction::Jump(vec![]));
+                        }
+                        if let Some(text) = &history.game.comment {
+                            list.comment(ui, text);
+                        }
+                        list.line(ui, vec![], 0, true);
+                        action = list.action;
+                    });
+                });
+            ui.separator();
+            export_controls(ui, &history, &mut export, &mut clipboard, text_color);
+            imported = import_controls(ui, &mut import, &export, &mut clipboard, text_color);
+        });
+
+    if let Some(game) = imported {
+        export.take_tags(&game);
+        history.load(game);
+        load_events.send(LoadFen(history.position().to_fen()));
+        return;
+    }
+
+    let moved = match action {
+        Some(MoveAction::Jump(path)) => {
+            history.current = path;
+            true
+        }
+        // the board stays on the same move, wherever that's gone in the tree
+        Some(MoveAction::Promote(path)) => history.edit(|game| game.promote(&path)),
+        Some(MoveAction::Delete(path)) => history.edit(|game| game.remove_variation(&path)),
+        None => false,
+    };
+    if moved {
+        load_events.send(LoadFen(history.position().to_fen()));
+    }
+}
+
+/// left and right step back and forward along the line the board's on, home and end
+/// go to the start and the end of it
+pub fn step_through_moves(
+    keys: Res<Input<KeyCode>>,
+    mut history: ResMut<MoveHistory>,
+    mut load_events: EventWriter<LoadFen>,
+) {
+    let path = if keys.just_pressed(KeyCode::Left) {
+        let mut path = history.current.clone();
+        path.pop();
+        path
+    } else if keys.just_pressed(KeyCode::Right) {
+        let mut path = history.current.clone();
+        if history
+            .game
+            .children(&path)
+            .is_some_and(|children| !children.is_empty())
+        {
+            path.push(0);
+        }
+        path
+    } else if keys.just_pressed(KeyCode::Home) {
+        vec![]
+    } else if keys.just_pressed(KeyCode::End) {
+        history.game.mainline_from(&history.current)
+    } else {
+        return;
+    };
+
+    if path == history.current {
+        // already there, and the board might have been set up since
+        return;
+    }
+    history.current = path;
+    load_events.send(LoadFen(history.position().to_fen()));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chessbored::pgn::read_pgn;
+
+    fn play(history: &mut MoveHistory, sans: &[&str]) {
+        for san in sans {
+            let on_board = history.position();
+            let mv = on_board.parse_san(san).unwrap();
+            history.play(&on_board, &mv);
+        }
+    }
+
+    fn current_sans(history: &MoveHistory) -> Vec<String> {
+        let current = &history.current;
+        (1..=current.len())
+            .map(|length| history.game.node(&current[..length]).unwrap().san.clone())
+            .collect()
+    }
+
+    // 1. e4 e5 (1... c5 2. Nf3 (2. Nc3)) 2. Nf3, with the board on `current`
+    fn sicilian_aside(current: &[usize]) -> MoveHistory {
+        let mut history = MoveHistory::default();
+        let pgn = "1. e4 e5 (1... c5 2. Nf3 (2. Nc3)) 2. Nf3 *";
+        history.load(read_pgn(pgn).remove(0).unwrap());
+        history.current = current.to_vec();
+        history
+    }
+
+    #[test]
+    fn a_different_move_is_a_variation() {
+        let mut history = sicilian_aside(&[0]);
+        play(&mut history, &["d5"]);
+        assert_eq!(history.current, [0, 2]);
+        assert_eq!(current_sans(&history), ["e4", "d5"]);
+
+        // one that's already there is followed instead
+        history.current = vec![0];
+        play(&mut history, &["c5"]);
+        assert_eq!(history.current, [0, 1]);
+        assert_eq!(history.game.children(&[0]).unwrap().len(), 3);
+    }
+
+    #[test]
+    fn promoting_keeps_the_board_on_its_move() {
+        // the board's on the variation that's promoted
+        let mut history = sicilian_aside(&[0, 1, 1]);
+        let before = history.position();
+        assert!(!history.edit(|game| game.promote(&[0, 1, 1])));
+        assert_eq!(history.current, [0, 0, 0]);
+        assert_eq!(current_sans(&history), ["e4", "c5", "Nc3"]);
+        assert_eq!(history.position(), before);
+
+        // the board's on the line that's no longer the main line
+        let mut history = sicilian_aside(&[0, 0, 0]);
+        assert!(!history.edit(|game| game.promote(&[0, 1])));
+        assert_eq!(history.current, [0, 1, 0]);
+        assert_eq!(current_sans(&history), ["e4", "e5", "Nf3"]);
+
+        // the board's on a move beside the promoted one
+        let mut history = sicilian_aside(&[0, 1, 0]);
+        assert!(!history.edit(|game| game.promote(&[0, 1, 1])));
+        assert_eq!(history.current, [0, 0, 1]);
+        assert_eq!(current_sans(&history), ["e4", "c5", "Nf3"]);
+    }
+
+    #[test]
+    fn deleting_moves_the_board_back_only_when_it_has_to() {
+        // another variation goes and the board stays, even if its path changes
+        let mut history = sicilian_aside(&[0, 0, 0]);
+        assert!(!history.edit(|game| game.remove_variation(&[0, 1])));
+        assert_eq!(current_sans(&history), ["e4", "e5", "Nf3"]);
+
+        let mut history = sicilian_aside(&[0, 1, 0]);
+        assert!(!history.edit(|game| game.remove_variation(&[0, 1, 1])));
+        assert_eq!(history.current, [0, 1, 0]);
+        assert_eq!(current_sans(&history), ["e4", "c5", "Nf3"]);
+
+        // the board's variation goes, so it goes back to where the variation branched off
+        let mut history = sicilian_aside(&[0, 1, 1]);
+        assert!(history.edit(|game| game.remove_variation(&[0, 1, 0])));
+        assert_eq!(history.current, [0]);
+        assert_eq!(history.position(), history.game.position_at(&[0]).unwrap());
+
+        // a main line move goes with everything after it
+        let mut history = sicilian_aside(&[0, 0, 0]);
+        assert!(history.edit(|game| game.remove_variation(&[0, 0])));
+        assert_eq!(history.current, [0]);
+        assert_eq!(current_sans(&history), ["e4"]);
+    }
+}
@@ -0,0 +1,103 @@
+use crate::export::PgnExport;
+use bevy::prelude::*;
+use bevy_egui::{egui, EguiClipboard};
+use chessbored::{game::Game, pgn::read_pgn};
+use std::fs;
+
+/// the games read from the last PGN loaded or pasted, to pick one from
+#[derive(Component, Default)]
+pub struct PgnImport {
+    pub games: Vec<Game>,
+    // games that couldn't be read, and files that couldn't be opened
+    pub errors: Vec<String>,
+    pub selected: Option<usize>,
+}
+
+impl PgnImport {
+    /// read every game in `text`, and the first of them if it's the only one
+    fn read(&mut self, text: &str) -> Option<Game> {
+        *self = PgnImport::default();
+        for (index, game) in read_pgn(text).into_iter().enumerate() {
+            match game {
+                Ok(game) => self.games.push(game),
+                Err(e) => self.errors.push(format!("game {}: {}", index + 1, e)),
+            }
+        }
+        if self.games.is_empty() && self.errors.is_empty() {
+            self.errors.push("there's no game to read".to_string());
+        }
+
+        if self.games.len() == 1 {
+            self.selected = Some(0);
+            return self.games.first().cloned();
+        }
+        None
+    }
+}
+
+// "3. Carlsen - Nepomniachtchi, World Championship"
+fn describe(index: usize, game: &Game) -> String {
+    let white = game.tag("White").unwrap_or("?");
+    let black = game.tag("Black").unwrap_or("?");
+    match game.tag("Event").filter(|event| *event != "?") {
+        Some(event) => format!("{}. {} - {}, {}", index + 1, white, black, event),
+        None => format!("{}. {} - {}", index + 1, white, black),
+    }
+}
+
+/// load and paste buttons, and a choice of game when there's more than one, for the
+/// side panel. gives back the game to step through once there is one
+pub fn import_controls(
+    ui: &mut egui::Ui,
+    import: &mut PgnImport,
+    export: &PgnExport,
+    clipboard: &mut EguiClipboard,
+    text_color: egui::Color32,
+) -> Option<Game> {
+    let error_color = egui::Color32::from_rgb(0x7a, 0x1f, 0x1f);
+    let mut chosen = None;
+
+    ui.horizontal(|ui| {
+        if ui.button("load pgn").clicked() {
+            match fs::read_to_string(&export.path) {
+                Ok(text) => chosen = import.read(&text),
+                Err(e) => {
+                    warn!("couldn't read {}: {}", export.path, e);
+                    *import = PgnImport {
+                        errors: vec![e.to_string()],
+                        ..default()
+                    };
+                }
+            }
+        }
+        if ui.button("paste pgn").clicked() {
+            chosen = import.read(&clipboard.get_contents().unwrap_or_default());
+        }
+    });
+
+    if import.games.len() > 1 {
+        let mut selected = import.selected;
+        let shown = match selected {
+            Some(index) => describe(index, &import.games[index]),
+            None => format!("{} games", import.games.len()),
+        };
+        egui::ComboBox::from_id_source("pgn games")
+            .selected_text(egui::RichText::new(shown).color(text_color))
+            .width(ui.available_width())
+            .show_ui(ui, |ui| {
+                for (index, game) in import.games.iter().enumerate() {
+                    ui.selectable_value(&mut selected, Some(index), describe(index, game));
+                }
+            });
+        if selected != import.selected {
+            import.selected = selected;
+            chosen = selected.and_then(|index| import.games.get(index).cloned());
+        }
+    }
+
+    for error in &import.errors {
+        ui.label(egui::RichText::new(error).monospace().color(error_color));
+    }
+
+    chosen
+}
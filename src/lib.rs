@@ -0,0 +1,12 @@
+//! the chess model behind chessbored: squares, pieces and positions, with FEN in and out
+//! legal move generation, moves written in standard algebraic notation, games as PGN and
+//! engines spoken to over UCI. nothing in here knows about bevy, so other tools can use
+//! it and it can be tested without a window.
+
+pub mod game;
+pub mod moves;
+pub mod pgn;
+pub mod position;
+pub mod san;
+pub mod square;
+pub mod uci;
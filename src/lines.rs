@@ -0,0 +1,171 @@
+use crate::{
+    board::BoardLayout,
+    engine::{score_text, EngineState},
+    pieces::PieceMaterialHandles,
+    types::WithActivePiece,
+};
+use bevy::{prelude::*, sprite::MaterialMesh2dBundle};
+use bevy_egui::egui;
+use chessbored::{
+    moves::Move,
+    position::{Position, Turn},
+};
+
+// how many moves of each line are shown, the rest get less likely anyway
+const LINE_MOVES: usize = 8;
+
+/// the position a hovered move in the engine's lines would leave, shown in place of
+/// the pieces on the board
+#[derive(Component, Default)]
+pub struct LinePreview(pub Option<Position>);
+
+/// a piece drawn for the preview, it can't be picked up
+#[derive(Component)]
+pub struct PreviewPiece;
+
+/// what's been done with the moves in the engine's lines, each given as the line up
+/// to and including the move
+#[derive(Default)]
+pub struct LineChoice {
+    pub hovered: Option<Vec<Move>>,
+    pub clicked: Option<Vec<Move>>,
+}
+
+// "850k" or "1.2M"
+fn rate(nps: u64) -> String {
+    if nps >= 1_000_000 {
+        format!("{:.1}M", nps as f64 / 1_000_000.0)
+    } else {
+        format!("{}k", nps / 1_000)
+    }
+}
+
+/// the engine's best lines, best first, each with its score and how deep it's been
+/// searched. the moves can be hovered and clicked while the lines are for the board
+/// as it is
+pub fn line_controls(
+    ui: &mut egui::Ui,
+    state: &EngineState,
+    on_board: bool,
+    text_color: egui::Color32,
+) -> LineChoice {
+    let mut choice = LineChoice::default();
+    let analysed = match &state.analysed {
+        Some(analysed) => analysed,
+        None => return choice,
+    };
+
+    let depth = state.lines.first().and_then(|line| line.depth);
+    let summary = match (depth, state.nps) {
+        (Some(depth), Some(nps)) => format!("depth {} | {} nodes/s", depth, rate(nps)),
+        (Some(depth), None) => format!("depth {}", depth),
+        (None, Some(nps)) => format!("{} nodes/s", rate(nps)),
+        (None, None) => "thinking".to_string(),
+    };
+    ui.label(egui::RichText::new(summary).monospace().color(text_color));
+
+    for line in state.lines.iter().filter(|line| !line.pv.is_empty()) {
+        let moves: Vec<Move> = line.pv.iter().take(LINE_MOVES).cloned().collect();
+        let sans = analysed.san_line(&moves);
+
+        ui.horizontal_wrapped(|ui| {
+            let score = line
+                .score
+                .map(|score| score_text(&score, &analysed.turn))
+                .unwrap_or_else(|| "?".to_string());
+            ui.label(
+                egui::RichText::new(score)
+                    .monospace()
+                    .strong()
+                    .color(text_color),
+            );
+            if let Some(depth) = line.depth {
+                ui.label(
+                    egui::RichText::new(format!("d{}", depth))
+                        .small()
+                        .color(text_color),
+                );
+            }
+
+            let mut number = analysed.fullmove_number;
+            let mut turn = analysed.turn;
+            for (index, san) in sans.iter().enumerate() {
+                let label = match turn {
+                    Turn::White => format!("{}. {}", number, san),
+                    _ if index == 0 => format!("{}... {}", number, san),
+                    _ => san.clone(),
+                };
+                if turn == Turn::Black {
+                    number += 1;
+                }
+                turn = turn.other();
+
+                let response = ui.add_enabled(
+                    on_board,
+                    egui::SelectableLabel::new(false, egui::RichText::new(label).monospace()),
+                );
+                if response.hovered() {
+                    choice.hovered = Some(moves[..=index].to_vec());
+                }
+                if response.clicked() {
+                    choice.clicked = Some(moves[..=index].to_vec());
+                }
+            }
+        });
+    }
+
+    choice
+}
+
+/// draw the previewed position over the board while there is one, with the pieces
+/// that are really there hidden underneath
+pub fn show_preview(
+    preview: Res<LinePreview>,
+    layout: Res<BoardLayout>,
+    piece_material_handles: Res<PieceMaterialHandles>,
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    preview_query: Query<Entity, With<PreviewPiece>>,
+    mut active_query: Query<&mut Visibility, WithActivePiece>,
+    // what's drawn now, and whether the board was flipped when it was
+    mut shown: Local<Option<(Position, bool)>>,
+) {
+    let wanted = preview
+        .0
+        .as_ref()
+        .map(|position| (position.clone(), layout.flipped));
+    // pieces put on the board while the preview's up are kept out of sight too, so
+    // this goes on every frame rather than only when the preview changes
+    if wanted.is_some() || *shown != wanted {
+        for mut visibility in active_query.iter_mut() {
+            visibility.is_visible = wanted.is_none();
+        }
+    }
+    if *shown == wanted {
+        return;
+    }
+
+    for entity in preview_query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+
+    if let Some((position, _)) = &wanted {
+        for (square, side) in position.pieces() {
+            let at = layout.square_center(&square);
+            commands
+                .spawn_bundle(MaterialMesh2dBundle {
+                    mesh: meshes
+                        .add(Mesh::from(shape::Quad {
+                            size: Vec2::splat(layout.square_size),
+                            ..default()
+                        }))
+                        .into(),
+                    transform: Transform::from_xyz(at.x, at.y, 0.0),
+                    material: piece_material_handles.for_side(side),
+                    ..default()
+                })
+                .insert(PreviewPiece);
+        }
+    }
+    *shown = wanted;
+}
@@ -1,31 +1,78 @@
-use bevy::{prelude::*, window::PresentMode};
+use bevy::{
+    prelude::*,
+    window::{PresentMode, WindowResizeConstraints},
+};
 use bevy_egui::EguiPlugin;
 use bevy_mod_picking::*;
-use fen::SavedFenState;
+use board::BoardLayout;
+use chessbored::position::Position;
+use engine::EngineState;
+use engine_settings::EngineSettingsWindow;
+use export::PgnExport;
+use fen::{FenErrorState, FenInput, LoadFen, SavedFenState};
+use history::MoveHistory;
+use import::PgnImport;
+use lines::LinePreview;
+use persist::{SavedData, Settings};
 use pieces::PieceMaterialHandles;
+use play::{Mode, PlayMove, Promotion};
+use undo::UndoHistory;
+use validation::PositionProblems;
 
 mod assets;
 mod board;
 mod camera;
 mod control_ux;
+mod engine;
+mod engine_settings;
+mod eval_bar;
+mod export;
 mod fen;
+mod history;
+mod import;
+mod lines;
+mod persist;
 mod pieces;
+mod play;
 mod state;
 mod types;
+mod undo;
+mod validation;
 
 pub fn main() {
     let clear_color_hex_string = "69696b";
     App::new()
         .insert_resource(assets::BoardAssets::default())
         .insert_resource(assets::TextAssets::default())
-        .insert_resource(types::Board::default())
+        .insert_resource(BoardLayout::default())
         .insert_resource(SavedFenState::default())
+        .insert_resource(Position::default())
+        .insert_resource(FenErrorState::default())
+        .insert_resource(FenInput::default())
+        .insert_resource(PositionProblems::default())
+        .insert_resource(SavedData::default())
+        .insert_resource(Settings::default())
         .insert_resource(PieceMaterialHandles::default())
+        .insert_resource(Mode::default())
+        .insert_resource(Promotion::default())
+        .insert_resource(MoveHistory::default())
+        .insert_resource(PgnExport::default())
+        .insert_resource(PgnImport::default())
+        .insert_resource(UndoHistory::default())
+        .insert_resource(EngineState::default())
+        .insert_resource(EngineSettingsWindow::default())
+        .insert_resource(LinePreview::default())
         .insert_resource(WindowDescriptor {
-            width: 680.,
+            width: 900.,
             height: 700.,
             title: "chessbored".to_string(),
             present_mode: PresentMode::Fifo,
+            resizable: true,
+            resize_constraints: WindowResizeConstraints {
+                min_width: 450.,
+                min_height: 350.,
+                ..default()
+            },
             ..default()
         })
         .insert_resource(ClearColor(
@@ -33,6 +80,8 @@ pub fn main() {
                 panic!("couldn't make hex color from {}", clear_color_hex_string)
             }),
         ))
+        .add_event::<LoadFen>()
+        .add_event::<PlayMove>()
         .add_state(state::ChessState::Setup)
         .add_plugins(DefaultPlugins)
         .add_plugin(PickingPlugin)
@@ -40,7 +89,11 @@ pub fn main() {
         .add_plugin(EguiPlugin)
         // .add_plugin(DebugCursorPickingPlugin)
         // .add_plugin(DebugEventsPickingPlugin)
-        .add_system_set(SystemSet::on_update(state::ChessState::Setup).with_system(setup))
+        .add_system_set(
+            SystemSet::on_update(state::ChessState::Setup)
+                .with_system(setup)
+                .with_system(persist::load),
+        )
         .add_system_set(
             SystemSet::on_update(state::ChessState::Loading).with_system(assets::load_assets),
         )
@@ -49,11 +102,23 @@ pub fn main() {
                 .with_system(board::setup_board)
                 .label("setup_board"),
         )
+        .add_system_set(
+            SystemSet::on_update(state::ChessState::Loaded)
+                .with_system(eval_bar::setup_eval_bar)
+                .label("setup_eval_bar")
+                .after("setup_board"),
+        )
+        .add_system_set(
+            SystemSet::on_update(state::ChessState::Loaded)
+                .with_system(board::setup_coordinates)
+                .label("setup_coordinates")
+                .after("setup_eval_bar"),
+        )
         .add_system_set(
             SystemSet::on_update(state::ChessState::Loaded)
                 .with_system(pieces::set_sprite_handles)
                 .label("piece_sprite_handles")
-                .after("setup_board"),
+                .after("setup_coordinates"),
         )
         .add_system_set(
             SystemSet::on_update(state::ChessState::Loaded)
@@ -63,25 +128,55 @@ pub fn main() {
         )
         .add_system_set(
             SystemSet::on_update(state::ChessState::Loaded)
-                .with_system(fen::spawn)
-                .label("fen")
+                .with_system(control_ux::setup_egui)
+                .label("setup_egui")
                 .after("piece_selection"),
         )
         .add_system_set(
             SystemSet::on_update(state::ChessState::Loaded)
                 .with_system(control_ux::spawn)
                 .label("control_ux")
-                .after("fen"),
+                .after("setup_egui"),
         )
         .add_system_set(
             SystemSet::on_update(state::ChessState::Loaded)
-                .with_system(camera::setup)
+                .with_system(persist::restore_last_board)
+                .label("restore_last_board")
                 .after("control_ux"),
         )
+        .add_system_set(
+            SystemSet::on_update(state::ChessState::Loaded)
+                .with_system(camera::setup)
+                .after("restore_last_board"),
+        )
+        .add_system_set(
+            SystemSet::on_update(state::ChessState::Running)
+                .with_system(board::fit_to_window)
+                .label("fit_to_window"),
+        )
+        .add_system_set(
+            SystemSet::on_update(state::ChessState::Running)
+                .with_system(control_ux::follow_layout)
+                .label("follow_layout")
+                .after("fit_to_window"),
+        )
+        .add_system_set(
+            SystemSet::on_update(state::ChessState::Running)
+                .with_system(control_ux::block_keys_while_typing)
+                .label("block_keys_while_typing")
+                .after("follow_layout"),
+        )
+        .add_system_set(
+            SystemSet::on_update(state::ChessState::Running)
+                .with_system(play::toggle_mode)
+                .label("toggle_mode")
+                .after("block_keys_while_typing"),
+        )
         .add_system_set(
             SystemSet::on_update(state::ChessState::Running)
                 .with_system(pieces::cancel_piece_movement)
-                .label("cancel_piece_movement"),
+                .label("cancel_piece_movement")
+                .after("toggle_mode"),
         )
         .add_system_set(
             SystemSet::on_update(state::ChessState::Running)
@@ -89,11 +184,23 @@ pub fn main() {
                 .label("starting_positions")
                 .after("cancel_piece_movement"),
         )
+        .add_system_set(
+            SystemSet::on_update(state::ChessState::Running)
+                .with_system(undo::undo_redo)
+                .label("undo_redo")
+                .after("starting_positions"),
+        )
+        .add_system_set(
+            SystemSet::on_update(state::ChessState::Running)
+                .with_system(history::step_through_moves)
+                .label("step_through_moves")
+                .after("undo_redo"),
+        )
         .add_system_set(
             SystemSet::on_update(state::ChessState::Running)
                 .with_system(pieces::selection)
                 .label("selection")
-                .after("starting_positions"),
+                .after("step_through_moves"),
         )
         .add_system_set(
             SystemSet::on_update(state::ChessState::Running)
@@ -107,17 +214,65 @@ pub fn main() {
                 .label("drop_piece")
                 .after("side_piece_selection"),
         )
+        .add_system_set(
+            SystemSet::on_update(state::ChessState::Running)
+                .with_system(board::flip_board)
+                .label("flip_board")
+                .after("drop_piece"),
+        )
+        .add_system_set(
+            SystemSet::on_update(state::ChessState::Running)
+                .with_system(board::toggle_coordinates)
+                .label("toggle_coordinates")
+                .after("flip_board"),
+        )
+        .add_system_set(
+            SystemSet::on_update(state::ChessState::Running)
+                .with_system(board::place_coordinates)
+                .label("place_coordinates")
+                .after("toggle_coordinates"),
+        )
+        .add_system_set(
+            SystemSet::on_update(state::ChessState::Running)
+                .with_system(fen::toggle_fen_fields)
+                .label("toggle_fen_fields")
+                .after("place_coordinates"),
+        )
         .add_system_set(
             SystemSet::on_update(state::ChessState::Running)
                 .with_system(fen::generate_fen)
                 .label("generate_fen")
-                .after("drop_piece"),
+                .after("toggle_fen_fields"),
+        )
+        .add_system_set(
+            SystemSet::on_update(state::ChessState::Running)
+                .with_system(validation::validate_position)
+                .label("validate_position")
+                .after("generate_fen"),
+        )
+        .add_system_set(
+            SystemSet::on_update(state::ChessState::Running)
+                .with_system(validation::mark_problem_squares)
+                .label("mark_problem_squares")
+                .after("validate_position"),
+        )
+        .add_system_set(
+            SystemSet::on_update(state::ChessState::Running)
+                .with_system(play::toggle_move_hints)
+                .label("toggle_move_hints")
+                .after("mark_problem_squares"),
+        )
+        .add_system_set(
+            SystemSet::on_update(state::ChessState::Running)
+                .with_system(play::mark_legal_moves)
+                .label("mark_legal_moves")
+                .after("toggle_move_hints"),
         )
         .add_system_set(
             SystemSet::on_update(state::ChessState::Running)
                 .with_system(fen::toggle_save_position)
                 .label("toggle_save_position")
-                .after("generate_fen"),
+                .after("mark_legal_moves"),
         )
         .add_system_set(
             SystemSet::on_update(state::ChessState::Running)
@@ -125,17 +280,83 @@ pub fn main() {
                 .label("populate_board_from_fen")
                 .after("toggle_save_position"),
         )
+        .add_system_set(
+            SystemSet::on_update(state::ChessState::Running)
+                .with_system(fen::paste_from_clipboard)
+                .label("paste_from_clipboard")
+                .after("populate_board_from_fen"),
+        )
         .add_system_set(
             SystemSet::on_update(state::ChessState::Running)
                 .with_system(fen::copy_to_clipboard)
                 .label("copy_to_clipboard")
-                .after("populate_board_from_fen"),
+                .after("paste_from_clipboard"),
+        )
+        .add_system_set(
+            SystemSet::on_update(state::ChessState::Running)
+                .with_system(fen::position_panel)
+                .label("position_panel")
+                .after("copy_to_clipboard"),
+        )
+        .add_system_set(
+            SystemSet::on_update(state::ChessState::Running)
+                .with_system(history::history_panel)
+                .label("history_panel")
+                .after("position_panel"),
+        )
+        .add_system_set(
+            SystemSet::on_update(state::ChessState::Running)
+                .with_system(engine::follow_board)
+                .label("follow_board")
+                .after("history_panel"),
+        )
+        .add_system_set(
+            SystemSet::on_update(state::ChessState::Running)
+                .with_system(engine::read_engine)
+                .label("read_engine")
+                .after("follow_board"),
+        )
+        .add_system_set(
+            SystemSet::on_update(state::ChessState::Running)
+                .with_system(eval_bar::update_eval_bar)
+                .label("update_eval_bar")
+                .after("read_engine"),
+        )
+        .add_system_set(
+            SystemSet::on_update(state::ChessState::Running)
+                .with_system(engine::engine_panel)
+                .label("engine_panel")
+                .after("update_eval_bar"),
+        )
+        .add_system_set(
+            SystemSet::on_update(state::ChessState::Running)
+                .with_system(engine_settings::engine_settings_window)
+                .label("engine_settings_window")
+                .after("engine_panel"),
+        )
+        .add_system_set(
+            SystemSet::on_update(state::ChessState::Running)
+                .with_system(lines::show_preview)
+                .label("show_preview")
+                .after("engine_settings_window"),
+        )
+        .add_system_set(
+            SystemSet::on_update(state::ChessState::Running)
+                .with_system(fen::load_fen)
+                .label("load_fen")
+                .after("show_preview"),
+        )
+        .add_system_set(
+            SystemSet::on_update(state::ChessState::Running)
+                .with_system(play::play_move)
+                .label("play_move")
+                .after("load_fen"),
         )
         .add_system_set(
             SystemSet::on_update(state::ChessState::Running)
                 .with_system(pieces::clear_board)
                 .label("clear_board")
-                .after("copy_to_clipboard"),
+                .after("play_move"),
         )
         .add_system_set(
             SystemSet::on_update(state::ChessState::Running)
@@ -143,10 +364,21 @@ pub fn main() {
                 .label("piece_movement")
                 .after("clear_board"),
         )
+        .add_system_set(
+            SystemSet::on_update(state::ChessState::Running)
+                .with_system(undo::record_edits)
+                .label("record_edits")
+                .after("piece_movement"),
+        )
+        .add_system_set(
+            SystemSet::on_update(state::ChessState::Running)
+                .with_system(persist::save)
+                .label("save")
+                .after("record_edits"),
+        )
         .run();
 }
 
-fn setup(mut board: ResMut<types::Board>, mut state: ResMut<State<state::ChessState>>) {
-    *board = board::board_map();
+fn setup(mut state: ResMut<State<state::ChessState>>) {
     state.set(state::ChessState::Loading).unwrap();
 }
@@ -0,0 +1,517 @@
+use crate::{
+    position::{en_passant_target, Kind, Position, Side, Turn},
+    square::{File, Rank, Square, STEPS},
+};
+use std::{fmt, str::FromStr};
+
+const KNIGHT_JUMPS: [(i8, i8); 8] = [
+    (1, 2),
+    (2, 1),
+    (2, -1),
+    (1, -2),
+    (-1, -2),
+    (-2, -1),
+    (-2, 1),
+    (-1, 2),
+];
+
+const ROOK_DIRECTIONS: [(i8, i8); 4] = [(0, 1), (1, 0), (0, -1), (-1, 0)];
+
+const BISHOP_DIRECTIONS: [(i8, i8); 4] = [(1, 1), (1, -1), (-1, -1), (-1, 1)];
+
+const PROMOTIONS: [Kind; 4] = [Kind::Queen, Kind::Rook, Kind::Bishop, Kind::Knight];
+
+/// a piece going from one square to another. castling is the king's two square move,
+/// and `promotion` is what a pawn reaching the last rank turns into
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Move {
+    pub from: Square,
+    pub to: Square,
+    pub promotion: Option<Kind>,
+}
+
+impl Move {
+    pub fn new(from: Square, to: Square) -> Move {
+        Move {
+            from,
+            to,
+            promotion: None,
+        }
+    }
+
+    pub fn promoting(from: Square, to: Square, kind: Kind) -> Move {
+        Move {
+            from,
+            to,
+            promotion: Some(kind),
+        }
+    }
+}
+
+/// long algebraic notation the way UCI engines write it, "e2e4" or "e7e8q"
+impl fmt::Display for Move {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        write!(fmt, "{}{}", self.from, self.to)?;
+        match self.promotion {
+            Some(Kind::Queen) => fmt.write_str("q"),
+            Some(Kind::Rook) => fmt.write_str("r"),
+            Some(Kind::Bishop) => fmt.write_str("b"),
+            Some(Kind::Knight) => fmt.write_str("n"),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// text that isn't two squares and an optional promotion letter, like "e7e8q"
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseMoveError;
+
+impl fmt::Display for ParseMoveError {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        fmt.write_str("a move is two squares followed by an optional promotion, like e7e8q")
+    }
+}
+
+impl std::error::Error for ParseMoveError {}
+
+impl FromStr for Move {
+    type Err = ParseMoveError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let from = s.get(0..2).ok_or(ParseMoveError)?;
+        let to = s.get(2..4).ok_or(ParseMoveError)?;
+        let promotion = match s.get(4..).ok_or(ParseMoveError)? {
+            "" => None,
+            "q" => Some(Kind::Queen),
+            "r" => Some(Kind::Rook),
+            "b" => Some(Kind::Bishop),
+            "n" => Some(Kind::Knight),
+            _ => return Err(ParseMoveError),
+        };
+
+        Ok(Move {
+            from: from.parse().map_err(|_| ParseMoveError)?,
+            to: to.parse().map_err(|_| ParseMoveError)?,
+            promotion,
+        })
+    }
+}
+
+fn side_of(turn: Turn, kind: Kind) -> Side {
+    match turn {
+        Turn::White => Side::White(kind),
+        Turn::Black => Side::Black(kind),
+    }
+}
+
+impl Position {
+    pub fn king_square(&self, turn: Turn) -> Option<Square> {
+        let king = side_of(turn, Kind::King);
+        self.pieces()
+            .find(|(_, side)| **side == king)
+            .map(|(square, _)| square)
+    }
+
+    /// squares holding a piece of `by` that attacks `target`
+    pub fn attackers(&self, target: &Square, by: Turn) -> Vec<Square> {
+        let is = |square: &Square, kinds: &[Kind]| match self.piece_at(square) {
+            Some(side) => side.turn() == by && kinds.contains(side.kind()),
+            None => false,
+        };
+
+        let mut found = vec![];
+
+        let pawn_rank = match by {
+            Turn::White => -1,
+            Turn::Black => 1,
+        };
+        for df in [-1, 1] {
+            found.extend(
+                target
+                    .offset(df, pawn_rank)
+                    .filter(|square| is(square, &[Kind::Pawn])),
+            );
+        }
+
+        for (df, dr) in KNIGHT_JUMPS {
+            found.extend(
+                target
+                    .offset(df, dr)
+                    .filter(|square| is(square, &[Kind::Knight])),
+            );
+        }
+
+        found.extend(
+            target
+                .neighbours()
+                .filter(|square| is(square, &[Kind::King])),
+        );
+
+        // slide out from the target until something's in the way
+        for (df, dr) in STEPS {
+            let sliders = if df == 0 || dr == 0 {
+                [Kind::Rook, Kind::Queen]
+            } else {
+                [Kind::Bishop, Kind::Queen]
+            };
+            let mut next = target.offset(df, dr);
+            while let Some(square) = next {
+                if self.piece_at(&square).is_some() {
+                    if is(&square, &sliders) {
+                        found.push(square);
+                    }
+                    break;
+                }
+                next = square.offset(df, dr);
+            }
+        }
+
+        found
+    }
+
+    pub fn is_attacked(&self, target: &Square, by: Turn) -> bool {
+        !self.attackers(target, by).is_empty()
+    }
+
+    /// whether the side to move's king is attacked
+    pub fn in_check(&self) -> bool {
+        self.king_square(self.turn)
+            .is_some_and(|king| self.is_attacked(&king, self.turn.other()))
+    }
+
+    pub fn is_checkmate(&self) -> bool {
+        self.in_check() && self.legal_moves().is_empty()
+    }
+
+    pub fn is_stalemate(&self) -> bool {
+        !self.in_check() && self.legal_moves().is_empty()
+    }
+
+    /// every move the side to move can make without leaving its own king attacked.
+    /// a side without a king can move anything anywhere it could otherwise go
+    pub fn legal_moves(&self) -> Vec<Move> {
+        let mut moves = vec![];
+        for (from, side) in self.pieces().filter(|(_, side)| side.turn() == self.turn) {
+            self.piece_moves(&from, side, &mut moves);
+        }
+
+        moves.retain(|mv| {
+            let mut after = self.clone();
+            after.play(mv);
+            match after.king_square(self.turn) {
+                Some(king) => !after.is_attacked(&king, self.turn.other()),
+                None => true,
+            }
+        });
+        moves
+    }
+
+    /// the legal moves for whatever's on `from`
+    pub fn legal_moves_from(&self, from: &Square) -> Vec<Move> {
+        self.legal_moves()
+            .into_iter()
+            .filter(|mv| mv.from == *from)
+            .collect()
+    }
+
+    pub fn is_legal(&self, mv: &Move) -> bool {
+        self.legal_moves().contains(mv)
+    }
+
+    /// makes a move, which is assumed to be legal: the piece on `from` goes to `to`,
+    /// taking whatever's there or passed en passant, bringing the rook along when it's
+    /// castling, and the side to move, castling rights, en passant target and clocks
+    /// all move on
+    pub fn play(&mut self, mv: &Move) {
+        let side = match self.remove(&mv.from) {
+            Some(side) => side,
+            None => return,
+        };
+        let us = side.turn();
+        let kind = side.kind().clone();
+        let mut captured = self.remove(&mv.to).is_some();
+
+        if kind == Kind::Pawn
+            && mv.from.file != mv.to.file
+            && !captured
+            && self.en_passant.as_ref() == Some(&mv.to)
+        {
+            // the pawn that's taken is beside the one taking it, not on the target
+            let passed = Square::new(mv.to.file, mv.from.rank);
+            captured = self.remove(&passed).is_some();
+        }
+
+        if kind == Kind::King && mv.from.file_distance(&mv.to) == 2 {
+            let (rook_from, rook_to) = if mv.to.file == File::G {
+                (File::H, File::F)
+            } else {
+                (File::A, File::D)
+            };
+            let rank = mv.from.rank;
+            if let Some(rook) = self.remove(&Square::new(rook_from, rank)) {
+                self.put(Square::new(rook_to, rank), rook);
+            }
+        }
+
+        self.en_passant = en_passant_target(&side, &mv.from, &mv.to).map(|(target, _)| target);
+
+        let placed = match &mv.promotion {
+            Some(promotion) if kind == Kind::Pawn => side_of(us, promotion.clone()),
+            _ => side,
+        };
+        self.put(mv.to, placed);
+
+        // once a king or rook has left home the right's gone for good
+        self.castling = self.castling_available();
+
+        if kind == Kind::Pawn || captured {
+            self.halfmove_clock = 0;
+        } else {
+            self.halfmove_clock += 1;
+        }
+        if us == Turn::Black {
+            self.fullmove_number += 1;
+        }
+        self.turn = us.other();
+    }
+
+    // moves for `side` on `from` that ignore whether its own king ends up attacked
+    fn piece_moves(&self, from: &Square, side: &Side, moves: &mut Vec<Move>) {
+        match side.kind() {
+            Kind::Pawn => self.pawn_moves(from, side.turn(), moves),
+            Kind::Knight => self.step_moves(from, side.turn(), &KNIGHT_JUMPS, moves),
+            Kind::Bishop => self.slide_moves(from, side.turn(), &BISHOP_DIRECTIONS, moves),
+            Kind::Rook => self.slide_moves(from, side.turn(), &ROOK_DIRECTIONS, moves),
+            Kind::Queen => self.slide_moves(from, side.turn(), &STEPS, moves),
+            Kind::King => {
+                self.step_moves(from, side.turn(), &STEPS, moves);
+                self.castling_moves(from, side.turn(), moves);
+            }
+        }
+    }
+
+    fn can_land_on(&self, square: &Square, us: Turn) -> bool {
+        match self.piece_at(square) {
+            Some(side) => side.turn() != us,
+            None => true,
+        }
+    }
+
+    fn step_moves(&self, from: &Square, us: Turn, steps: &[(i8, i8)], moves: &mut Vec<Move>) {
+        for (df, dr) in steps {
+            if let Some(to) = from.offset(*df, *dr) {
+                if self.can_land_on(&to, us) {
+                    moves.push(Move::new(*from, to));
+                }
+            }
+        }
+    }
+
+    fn slide_moves(&self, from: &Square, us: Turn, directions: &[(i8, i8)], moves: &mut Vec<Move>) {
+        for (df, dr) in directions {
+            let mut next = from.offset(*df, *dr);
+            while let Some(to) = next {
+                let occupied = self.piece_at(&to).is_some();
+                if self.can_land_on(&to, us) {
+                    moves.push(Move::new(*from, to));
+                }
+                if occupied {
+                    break;
+                }
+                next = to.offset(*df, *dr);
+            }
+        }
+    }
+
+    fn pawn_moves(&self, from: &Square, us: Turn, moves: &mut Vec<Move>) {
+        let (forward, start_rank, last_rank) = match us {
+            Turn::White => (1, Rank::Two, Rank::Eight),
+            Turn::Black => (-1, Rank::Seven, Rank::One),
+        };
+
+        let mut push = |to: Square| {
+            if to.rank == last_rank {
+                for kind in PROMOTIONS {
+                    moves.push(Move::promoting(*from, to, kind));
+                }
+            } else {
+                moves.push(Move::new(*from, to));
+            }
+        };
+
+        if let Some(one) = from.offset(0, forward) {
+            if self.piece_at(&one).is_none() {
+                if from.rank == start_rank {
+                    if let Some(two) = one.offset(0, forward) {
+                        if self.piece_at(&two).is_none() {
+                            push(two);
+                        }
+                    }
+                }
+                push(one);
+            }
+        }
+
+        let en_passant = self.valid_en_passant();
+        for df in [-1, 1] {
+            if let Some(to) = from.offset(df, forward) {
+                let takes = match self.piece_at(&to) {
+                    Some(side) => side.turn() != us,
+                    None => en_passant == Some(&to),
+                };
+                if takes {
+                    push(to);
+                }
+            }
+        }
+    }
+
+    fn castling_moves(&self, from: &Square, us: Turn, moves: &mut Vec<Move>) {
+        let rights = self.castling_available();
+        let (kingside, queenside, rank) = match us {
+            Turn::White => (rights.white_kingside, rights.white_queenside, Rank::One),
+            Turn::Black => (rights.black_kingside, rights.black_queenside, Rank::Eight),
+        };
+        if (!kingside && !queenside) || from.rank != rank || from.file != File::E {
+            return;
+        }
+
+        let them = us.other();
+        let square = |file: File| Square::new(file, rank);
+        let empty = |files: &[File]| {
+            files
+                .iter()
+                .all(|file| self.piece_at(&square(*file)).is_none())
+        };
+        let safe = |files: &[File]| {
+            files
+                .iter()
+                .all(|file| !self.is_attacked(&square(*file), them))
+        };
+
+        // the king can't castle out of, through or into check
+        if kingside && empty(&[File::F, File::G]) && safe(&[File::E, File::F, File::G]) {
+            moves.push(Move::new(*from, square(File::G)));
+        }
+        if queenside && empty(&[File::B, File::C, File::D]) && safe(&[File::E, File::D, File::C]) {
+            moves.push(Move::new(*from, square(File::C)));
+        }
+    }
+}
+
+/// the number of move sequences `depth` moves long from `position`,
+/// the usual way of checking a move generator against known counts
+pub fn perft(position: &Position, depth: u32) -> u64 {
+    if depth == 0 {
+        return 1;
+    }
+
+    let moves = position.legal_moves();
+    if depth == 1 {
+        return moves.len() as u64;
+    }
+
+    moves
+        .iter()
+        .map(|mv| {
+            let mut next = position.clone();
+            next.play(mv);
+            perft(&next, depth - 1)
+        })
+        .sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::position::STARTING_FEN;
+
+    // reference counts from https://www.chessprogramming.org/Perft_Results
+    fn assert_perft(fen: &str, counts: &[u64]) {
+        let position = Position::from_fen(fen).unwrap();
+        for (depth, count) in counts.iter().enumerate() {
+            let depth = depth as u32 + 1;
+            assert_eq!(
+                perft(&position, depth),
+                *count,
+                "{} at depth {}",
+                fen,
+                depth
+            );
+        }
+    }
+
+    #[test]
+    fn perft_starting_position() {
+        assert_perft(STARTING_FEN, &[20, 400, 8_902, 197_281]);
+    }
+
+    #[test]
+    fn perft_kiwipete() {
+        assert_perft(
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            &[48, 2_039, 97_862],
+        );
+    }
+
+    #[test]
+    fn perft_position_3() {
+        assert_perft(
+            "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+            &[14, 191, 2_812, 43_238],
+        );
+    }
+
+    #[test]
+    fn perft_position_4() {
+        assert_perft(
+            "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1",
+            &[6, 264, 9_467],
+        );
+    }
+
+    #[test]
+    fn perft_position_4_mirrored() {
+        assert_perft(
+            "r2q1rk1/pP1p2pp/Q4n2/bbp1p3/Np6/1B3NBn/pPPP1PPP/R3K2R b KQ - 0 1",
+            &[6, 264, 9_467],
+        );
+    }
+
+    #[test]
+    fn perft_position_5() {
+        assert_perft(
+            "rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8",
+            &[44, 1_486, 62_379],
+        );
+    }
+
+    #[test]
+    fn perft_position_6() {
+        assert_perft(
+            "r4rk1/1pp1qppp/p1np1n2/2b1p1B1/2B1P1b1/P1NP1N2/1PP1QPPP/R4RK1 w - - 0 10",
+            &[46, 2_079, 89_890],
+        );
+    }
+
+    #[test]
+    fn play_updates_the_other_fields() {
+        let mut position = Position::starting();
+        for mv in ["e2e4", "c7c5", "g1f3"] {
+            position.play(&mv.parse().unwrap());
+        }
+        assert_eq!(
+            position.to_fen(),
+            "rnbqkbnr/pp1ppppp/8/2p5/4P3/5N2/PPPP1PPP/RNBQKB1R b KQkq - 1 2"
+        );
+    }
+
+    #[test]
+    fn moves_round_trip_through_uci() {
+        for text in ["e2e4", "e7e8q", "a2a1n"] {
+            assert_eq!(text.parse::<Move>().unwrap().to_string(), text);
+        }
+        assert!("e2".parse::<Move>().is_err());
+        assert!("e7e8k".parse::<Move>().is_err());
+    }
+}
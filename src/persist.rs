@@ -0,0 +1,183 @@
+use crate::{
+    engine_settings::EngineProfile,
+    fen::{LoadFen, SavedFenState, SavedPosition},
+};
+use bevy::{app::AppExit, prelude::*, window::WindowCloseRequested};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use std::{collections::BTreeMap, fs, io, path::PathBuf};
+
+const APP_DIR: &str = "chessbored";
+
+// don't write the board more than this often while pieces are moving around
+const WRITE_INTERVAL_SECONDS: f64 = 1.0;
+
+/// preferences, kept in the config directory
+#[derive(Component, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    pub restore_last_board: bool,
+    pub show_coordinates: bool,
+    // legal move hints for the piece in hand while setting up, they're always on in play
+    pub show_setup_moves: bool,
+    // the UCI engine to start from the engine panel
+    pub engine_path: String,
+    // how many of its best lines the engine's asked for
+    pub engine_lines: u32,
+    // engine options that have been changed from what each engine starts with, by the
+    // engine's path so one engine's options aren't sent to another
+    pub engine_options: BTreeMap<String, BTreeMap<String, String>>,
+    pub engine_profiles: Vec<EngineProfile>,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Settings {
+            restore_last_board: true,
+            show_coordinates: true,
+            show_setup_moves: false,
+            engine_path: String::new(),
+            engine_lines: 3,
+            engine_options: BTreeMap::new(),
+            engine_profiles: vec![],
+        }
+    }
+}
+
+impl Settings {
+    /// the options that have been changed for the engine at `engine_path`
+    pub fn current_engine_options(&self) -> BTreeMap<String, String> {
+        self.engine_options
+            .get(self.engine_path.trim())
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    pub fn current_engine_options_mut(&mut self) -> &mut BTreeMap<String, String> {
+        let path = self.engine_path.trim().to_string();
+        self.engine_options.entry(path).or_default()
+    }
+}
+
+/// saved positions and the board as it was last seen, kept in the data directory
+#[derive(Component, Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SavedData {
+    pub saved: Vec<SavedPosition>,
+    pub saved_count: usize,
+    pub last_board: String,
+}
+
+fn settings_path() -> Option<PathBuf> {
+    dirs::config_dir().map(|dir| dir.join(APP_DIR).join("settings.ron"))
+}
+
+fn data_path() -> Option<PathBuf> {
+    dirs::data_dir().map(|dir| dir.join(APP_DIR).join("positions.ron"))
+}
+
+fn read<T: DeserializeOwned + Default>(path: Option<PathBuf>) -> T {
+    let path = match path {
+        Some(path) => path,
+        None => return T::default(),
+    };
+
+    match fs::read_to_string(&path) {
+        Ok(contents) => ron::from_str(&contents).unwrap_or_else(|e| {
+            warn!("couldn't read {}: {}", path.display(), e);
+            T::default()
+        }),
+        // nothing's been written yet
+        Err(e) if e.kind() == io::ErrorKind::NotFound => T::default(),
+        Err(e) => {
+            warn!("couldn't read {}: {}", path.display(), e);
+            T::default()
+        }
+    }
+}
+
+fn write<T: Serialize>(path: Option<PathBuf>, value: &T) {
+    let path = match path {
+        Some(path) => path,
+        None => return,
+    };
+
+    let result = ron::ser::to_string_pretty(value, ron::ser::PrettyConfig::default())
+        .map_err(|e| e.to_string())
+        .and_then(|contents| {
+            if let Some(dir) = path.parent() {
+                fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+            }
+            fs::write(&path, contents).map_err(|e| e.to_string())
+        });
+
+    if let Err(e) = result {
+        warn!("couldn't write {}: {}", path.display(), e);
+    }
+}
+
+pub fn load(
+    mut saved_fen: ResMut<SavedFenState>,
+    mut saved_data: ResMut<SavedData>,
+    mut settings: ResMut<Settings>,
+) {
+    *settings = read(settings_path());
+    *saved_data = read(data_path());
+
+    saved_fen.saved = saved_data.saved.clone();
+    saved_fen.saved_count = saved_data.saved_count;
+}
+
+pub fn restore_last_board(
+    saved_data: Res<SavedData>,
+    settings: Res<Settings>,
+    mut load_events: EventWriter<LoadFen>,
+) {
+    if settings.restore_last_board && !saved_data.last_board.is_empty() {
+        load_events.send(LoadFen(saved_data.last_board.clone()));
+    }
+}
+
+/// write settings as soon as they change and saved positions as soon as they do, the
+/// board as it is no more than once an interval, and anything still waiting on the way out
+pub fn save(
+    time: Res<Time>,
+    saved_fen: Res<SavedFenState>,
+    settings: Res<Settings>,
+    mut saved_data: ResMut<SavedData>,
+    mut exit_events: EventReader<AppExit>,
+    mut close_events: EventReader<WindowCloseRequested>,
+    // when saving started, and when the board was last written
+    mut started: Local<Option<f64>>,
+    mut last_board_write: Local<f64>,
+    // the settings have been looked at since they were loaded
+    mut settings_seen: Local<bool>,
+) {
+    // the settings that were just read in don't need writing back
+    if settings.is_changed() && *settings_seen {
+        write(settings_path(), &*settings);
+    }
+    *settings_seen = true;
+
+    let exiting = exit_events.iter().count() + close_events.iter().count() > 0;
+    let now = time.seconds_since_startup();
+    // the first interval gives the restored board time to land before it's written over
+    let settled = now - *started.get_or_insert(now) >= WRITE_INTERVAL_SECONDS;
+    let board_due = settled
+        && !saved_fen.curr.is_empty()
+        && (exiting || now - *last_board_write >= WRITE_INTERVAL_SECONDS);
+
+    let saved_changed =
+        saved_fen.saved != saved_data.saved || saved_fen.saved_count != saved_data.saved_count;
+    let board_changed = board_due && saved_fen.curr != saved_data.last_board;
+    if !saved_changed && !board_changed {
+        return;
+    }
+
+    if board_changed {
+        saved_data.last_board = saved_fen.curr.clone();
+        *last_board_write = now;
+    }
+    saved_data.saved = saved_fen.saved.clone();
+    saved_data.saved_count = saved_fen.saved_count;
+    write(data_path(), &*saved_data);
+}
@@ -0,0 +1,828 @@
+use crate::{
+    game::{Game, Node},
+    position::{FenError, Position, Turn, STARTING_FEN},
+};
+use std::{
+    fmt::{self, Write as _},
+    iter::Peekable,
+    vec,
+};
+
+/// the tags every PGN game carries, in the order they're written
+pub const SEVEN_TAG_ROSTER: [&str; 7] =
+    ["Event", "Site", "Date", "Round", "White", "Black", "Result"];
+
+// export format keeps every line shorter than 80 characters
+const LINE_WIDTH: usize = 79;
+
+/// a `[Name "value"]` pair from the top of a game
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Tag {
+    pub name: String,
+    pub value: String,
+}
+
+impl Tag {
+    pub fn new(name: &str, value: &str) -> Tag {
+        Tag {
+            name: name.to_string(),
+            value: value.to_string(),
+        }
+    }
+}
+
+/// the seven tag roster with the values PGN uses for "not known"
+pub fn seven_tag_roster() -> Vec<Tag> {
+    SEVEN_TAG_ROSTER
+        .iter()
+        .map(|name| {
+            let value = match *name {
+                "Date" => "????.??.??",
+                "Result" => "*",
+                _ => "?",
+            };
+            Tag::new(name, value)
+        })
+        .collect()
+}
+
+/// a game in PGN export format: the roster, any other tags, a SetUp and FEN pair when
+/// it doesn't start from the starting position, then the moves with their comments,
+/// annotations and variations, wrapped to fit and ended with the result
+pub fn write_pgn(game: &Game) -> String {
+    let mut pgn = String::new();
+    for tag in export_tags(&game.tags, &game.start) {
+        let _ = writeln!(pgn, "[{} \"{}\"]", tag.name, escape(&tag.value));
+    }
+    pgn.push('\n');
+
+    let mut tokens = vec![];
+    if let Some(comment) = &game.comment {
+        push_comment(&mut tokens, comment);
+    }
+    movetext(&mut tokens, &game.start, &game.children);
+    tokens.push(game.tag("Result").unwrap_or("*").to_string());
+    pgn.push_str(&wrap(&tokens));
+    pgn.push('\n');
+
+    pgn
+}
+
+fn export_tags(tags: &[Tag], start: &Position) -> Vec<Tag> {
+    let value = |name: &str| tags.iter().find(|tag| tag.name == name);
+    let roster = seven_tag_roster()
+        .into_iter()
+        .map(|default| value(&default.name).cloned().unwrap_or(default));
+
+    // the position header is worked out from `start`, whatever the tags say
+    let others = tags
+        .iter()
+        .filter(|tag| {
+            !SEVEN_TAG_ROSTER.contains(&tag.name.as_str())
+                && tag.name != "SetUp"
+                && tag.name != "FEN"
+        })
+        .cloned();
+
+    let fen = start.to_fen();
+    let setup = if fen == STARTING_FEN {
+        vec![]
+    } else {
+        vec![Tag::new("SetUp", "1"), Tag::new("FEN", &fen)]
+    };
+
+    roster.chain(setup).chain(others).collect()
+}
+
+fn escape(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+// a word at a time so long comments wrap like everything else
+fn push_comment(tokens: &mut Vec<String>, comment: &str) {
+    let words: Vec<&str> = comment.split_whitespace().collect();
+    match words.as_slice() {
+        [] => tokens.push("{}".to_string()),
+        [only] => tokens.push(format!("{{{}}}", only)),
+        [first, middle @ .., last] => {
+            tokens.push(format!("{{{}", first));
+            tokens.extend(middle.iter().map(|word| word.to_string()));
+            tokens.push(format!("{}}}", last));
+        }
+    }
+}
+
+// the line from `position` with its variations in brackets after the move they're
+// alternatives to. move numbers go ahead of white's moves, and ahead of black's when
+// something else has come between it and white's
+fn movetext(tokens: &mut Vec<String>, position: &Position, children: &[Node]) {
+    let mut position = position.clone();
+    let mut children = children;
+    let mut interrupted = true;
+
+    while let Some(node) = children.first() {
+        push_move(tokens, &position, node, interrupted);
+        interrupted = node.comment.is_some();
+
+        for alternative in &children[1..] {
+            let mut variation = vec![];
+            movetext(&mut variation, &position, std::slice::from_ref(alternative));
+            if let Some(first) = variation.first_mut() {
+                first.insert(0, '(');
+            }
+            if let Some(last) = variation.last_mut() {
+                last.push(')');
+            }
+            tokens.extend(variation);
+            interrupted = true;
+        }
+
+        position.play(&node.mv);
+        children = &node.children;
+    }
+}
+
+fn push_move(tokens: &mut Vec<String>, position: &Position, node: &Node, interrupted: bool) {
+    let mut interrupted = interrupted;
+    if let Some(before) = &node.before {
+        push_comment(tokens, before);
+        interrupted = true;
+    }
+    // the number's kept in the same token as its move so they're never wrapped apart
+    tokens.push(match position.turn {
+        Turn::White => format!("{}. {}", position.fullmove_number, node.san),
+        Turn::Black if interrupted => format!("{}... {}", position.fullmove_number, node.san),
+        Turn::Black => node.san.clone(),
+    });
+    tokens.extend(node.nags.iter().map(|nag| format!("${}", nag)));
+    if let Some(comment) = &node.comment {
+        push_comment(tokens, comment);
+    }
+}
+
+fn wrap(tokens: &[String]) -> String {
+    let mut text = String::new();
+    let mut line_length = 0;
+
+    for token in tokens {
+        let length = token.chars().count();
+        if line_length > 0 && line_length + 1 + length > LINE_WIDTH {
+            text.push('\n');
+            line_length = 0;
+        } else if line_length > 0 {
+            text.push(' ');
+            line_length += 1;
+        }
+        text.push_str(token);
+        line_length += length;
+    }
+
+    text
+}
+
+/// every game in `text`, in order. a game that can't be read doesn't stop the ones
+/// after it
+pub fn read_pgn(text: &str) -> Vec<Result<Game, PgnError>> {
+    let mut tokens = tokenize(text).into_iter().peekable();
+    let mut games = vec![];
+
+    while tokens.peek().is_some() {
+        let mut tags = vec![];
+        while let Some(Token::Tag(_)) = tokens.peek() {
+            if let Some(Token::Tag(tag)) = tokens.next() {
+                tags.push(tag);
+            }
+        }
+
+        match read_game(tags, &mut tokens) {
+            Ok(Some(game)) => games.push(Ok(game)),
+            // whatever was left at the end wasn't a game
+            Ok(None) => {}
+            Err(error) => {
+                // on to the next game
+                while let Some(token) = tokens.next_if(|token| !matches!(token, Token::Tag(_))) {
+                    if let Token::Result(_) = token {
+                        break;
+                    }
+                }
+                games.push(Err(error));
+            }
+        }
+    }
+
+    games
+}
+
+/// why a game in a PGN couldn't be read
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PgnError {
+    Fen(FenError),
+    // a move that isn't legal where it's played, or could be more than one move
+    IllegalMove {
+        number: u32,
+        turn: Turn,
+        san: String,
+    },
+    Unclosed(&'static str),
+    // a ")" with no "(", or a "(" before there's a move to give an alternative to
+    MisplacedVariation,
+    Unexpected(char),
+}
+
+impl fmt::Display for PgnError {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Fen(error) => write!(fmt, "the FEN tag isn't a position: {}", error),
+            Self::IllegalMove { number, turn, san } => {
+                let dots = match turn {
+                    Turn::White => ".",
+                    Turn::Black => "...",
+                };
+                write!(fmt, "{}{} {} can't be played there", number, dots, san)
+            }
+            Self::Unclosed(what) => write!(fmt, "a {} isn't closed", what),
+            Self::MisplacedVariation => {
+                fmt.write_str("a variation has nothing to be an alternative to")
+            }
+            Self::Unexpected(c) => write!(fmt, "'{}' doesn't belong in a game", c),
+        }
+    }
+}
+
+impl std::error::Error for PgnError {}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Token {
+    Tag(Tag),
+    Comment(String),
+    Nag(u8),
+    Open,
+    Close,
+    Result(String),
+    Move(String),
+    Invalid(PgnError),
+}
+
+// where moves are being added: after the node at `path`, from `position`. `previous`
+// is where the last move was played from, which is where a variation on it starts
+struct Line {
+    path: Vec<usize>,
+    position: Position,
+    previous: Option<(Vec<usize>, Position)>,
+}
+
+fn read_game(
+    tags: Vec<Tag>,
+    tokens: &mut Peekable<vec::IntoIter<Token>>,
+) -> Result<Option<Game>, PgnError> {
+    let start = match tags.iter().find(|tag| tag.name == "FEN") {
+        Some(tag) => Position::from_fen(&tag.value).map_err(PgnError::Fen)?,
+        None => Position::starting(),
+    };
+    let mut anything = !tags.is_empty();
+    let mut game = Game {
+        tags,
+        ..Game::new(start.clone())
+    };
+
+    let mut line = Line {
+        path: vec![],
+        position: start,
+        previous: None,
+    };
+    let mut outer: Vec<Line> = vec![];
+    // a comment that's waiting for the move it comes before
+    let mut before: Option<String> = None;
+
+    // the next game's tags end this one if it had no result
+    while !matches!(tokens.peek(), None | Some(Token::Tag(_))) {
+        anything = true;
+        match tokens.next() {
+            Some(Token::Comment(text)) => match game.node_mut(&line.path) {
+                Some(node) if line.previous.is_some() => append(&mut node.comment, text),
+                _ => append(&mut before, text),
+            },
+            Some(Token::Nag(nag)) => {
+                if let Some(node) = game.node_mut(&line.path) {
+                    node.nags.push(nag);
+                }
+            }
+            Some(Token::Move(san)) => {
+                let mv = line.position.parse_san(&san).ok_or(PgnError::IllegalMove {
+                    number: line.position.fullmove_number,
+                    turn: line.position.turn,
+                    san,
+                })?;
+                let path = game
+                    .add_move(&line.path, &mv)
+                    .ok_or(PgnError::MisplacedVariation)?;
+                if let (Some(node), Some(text)) = (game.node_mut(&path), before.take()) {
+                    node.before = Some(text);
+                }
+                let from = line.position.clone();
+                line.position.play(&mv);
+                line.previous = Some((std::mem::replace(&mut line.path, path), from));
+            }
+            Some(Token::Open) => {
+                let (path, position) = line.previous.clone().ok_or(PgnError::MisplacedVariation)?;
+                outer.push(line);
+                line = Line {
+                    path,
+                    position,
+                    previous: None,
+                };
+            }
+            Some(Token::Close) => {
+                line = outer.pop().ok_or(PgnError::MisplacedVariation)?;
+            }
+            Some(Token::Result(result)) => {
+                if game.tag("Result").is_none() {
+                    game.tags.push(Tag::new("Result", &result));
+                }
+                break;
+            }
+            Some(Token::Invalid(error)) => return Err(error),
+            Some(Token::Tag(_)) | None => {}
+        }
+    }
+
+    if !outer.is_empty() {
+        return Err(PgnError::Unclosed("variation"));
+    }
+    if game.children.is_empty() {
+        game.comment = before;
+    }
+
+    Ok(anything.then_some(game))
+}
+
+fn append(comment: &mut Option<String>, text: String) {
+    *comment = Some(match comment.take() {
+        Some(earlier) => format!("{} {}", earlier, text),
+        None => text,
+    });
+}
+
+fn tokenize(text: &str) -> Vec<Token> {
+    let chars: Vec<char> = text.chars().collect();
+    let mut tokens = vec![];
+    let mut i = 0;
+
+    // everything from `i` up to the first `end`, and where to carry on from
+    let until = |i: usize, end: char| -> Option<(String, usize)> {
+        let length = chars[i..].iter().position(|c| *c == end)?;
+        Some((chars[i..i + length].iter().collect(), i + length + 1))
+    };
+
+    while i < chars.len() {
+        let c = chars[i];
+        match c {
+            c if c.is_whitespace() => i += 1,
+            // escaped lines are for other programs
+            '%' if i == 0 || chars[i - 1] == '\n' => {
+                i = until(i, '\n').map_or(chars.len(), |(_, next)| next);
+            }
+            ';' => {
+                let (comment, next) = until(i + 1, '\n')
+                    .unwrap_or_else(|| (chars[i + 1..].iter().collect(), chars.len()));
+                tokens.push(Token::Comment(comment.trim().to_string()));
+                i = next;
+            }
+            '{' => match until(i + 1, '}') {
+                Some((comment, next)) => {
+                    tokens.push(Token::Comment(comment.trim().to_string()));
+                    i = next;
+                }
+                None => {
+                    tokens.push(Token::Invalid(PgnError::Unclosed("comment")));
+                    break;
+                }
+            },
+            '[' => match read_tag(&chars[i + 1..]) {
+                Some((tag, length)) => {
+                    tokens.push(Token::Tag(tag));
+                    i += 1 + length;
+                }
+                None => {
+                    tokens.push(Token::Invalid(PgnError::Unclosed("tag")));
+                    i = until(i, '\n').map_or(chars.len(), |(_, next)| next);
+                }
+            },
+            '(' => {
+                tokens.push(Token::Open);
+                i += 1;
+            }
+            ')' => {
+                tokens.push(Token::Close);
+                i += 1;
+            }
+            '*' => {
+                tokens.push(Token::Result("*".to_string()));
+                i += 1;
+            }
+            '.' => i += 1,
+            '$' => {
+                let digits: String = chars[i + 1..]
+                    .iter()
+                    .take_while(|c| c.is_ascii_digit())
+                    .collect();
+                if let Ok(nag) = digits.parse() {
+                    tokens.push(Token::Nag(nag));
+                }
+                i += 1 + digits.len();
+            }
+            '!' | '?' => {
+                let marks: String = chars[i..]
+                    .iter()
+                    .take_while(|c| matches!(c, '!' | '?'))
+                    .collect();
+                let nag = match marks.as_str() {
+                    "!" => Some(1),
+                    "?" => Some(2),
+                    "!!" => Some(3),
+                    "??" => Some(4),
+                    "!?" => Some(5),
+                    "?!" => Some(6),
+                    _ => None,
+                };
+                tokens.extend(nag.map(Token::Nag));
+                i += marks.len();
+            }
+            c if c.is_ascii_alphanumeric() => {
+                let symbol: String = chars[i..]
+                    .iter()
+                    .take_while(|c| c.is_ascii_alphanumeric() || "_+#=:-/".contains(**c))
+                    .collect();
+                i += symbol.len();
+                match symbol.as_str() {
+                    "1-0" | "0-1" | "1/2-1/2" => tokens.push(Token::Result(symbol)),
+                    // a move number, the dots after it are skipped on their own
+                    _ if symbol.chars().all(|c| c.is_ascii_digit()) => {}
+                    _ => tokens.push(Token::Move(symbol)),
+                }
+            }
+            c => {
+                tokens.push(Token::Invalid(PgnError::Unexpected(c)));
+                i += 1;
+            }
+        }
+    }
+
+    tokens
+}
+
+// `Name "value"]`, and how many characters that took
+fn read_tag(chars: &[char]) -> Option<(Tag, usize)> {
+    let mut i = 0;
+    let skip_space = |i: &mut usize| {
+        while chars.get(*i).is_some_and(|c| c.is_whitespace()) {
+            *i += 1;
+        }
+    };
+
+    skip_space(&mut i);
+    let name: String = chars[i..]
+        .iter()
+        .take_while(|c| c.is_ascii_alphanumeric() || **c == '_')
+        .collect();
+    i += name.len();
+    skip_space(&mut i);
+    if name.is_empty() || chars.get(i) != Some(&'"') {
+        return None;
+    }
+    i += 1;
+
+    let mut value = String::new();
+    loop {
+        match chars.get(i)? {
+            '\\' => {
+                value.push(*chars.get(i + 1)?);
+                i += 2;
+            }
+            '"' => {
+                i += 1;
+                break;
+            }
+            c => {
+                value.push(*c);
+                i += 1;
+            }
+        }
+    }
+
+    skip_space(&mut i);
+    if chars.get(i) != Some(&']') {
+        return None;
+    }
+
+    Some((Tag::new(&name, &value), i + 1))
+}
+
+/// how the common annotations are written after a move, "!" for $1 and so on
+pub fn nag_glyph(nag: u8) -> Option<&'static str> {
+    match nag {
+        1 => Some("!"),
+        2 => Some("?"),
+        3 => Some("!!"),
+        4 => Some("??"),
+        5 => Some("!?"),
+        6 => Some("?!"),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const OPERA_GAME: [&str; 33] = [
+        "e4", "e5", "Nf3", "d6", "d4", "Bg4", "dxe5", "Bxf3", "Qxf3", "dxe5", "Bc4", "Nf6", "Qb3",
+        "Qe7", "Nc3", "c6", "Bg5", "b5", "Nxb5", "cxb5", "Bxb5+", "Nbd7", "O-O-O", "Rd8", "Rxd7",
+        "Rxd7", "Rd1", "Qe6", "Bxd7+", "Nxd7", "Qb8+", "Nxb8", "Rd8#",
+    ];
+
+    // the main line `sans` played from `start`
+    fn game(start: Position, sans: &[&str]) -> Game {
+        let mut game = Game::new(start);
+        let mut path = vec![];
+        for san in sans {
+            let mv = game.position_at(&path).unwrap().parse_san(san).unwrap();
+            path = game.add_move(&path, &mv).unwrap();
+        }
+        game
+    }
+
+    fn tag_lines(pgn: &str) -> Vec<&str> {
+        pgn.lines()
+            .take_while(|line| line.starts_with('['))
+            .collect()
+    }
+
+    fn movetext(pgn: &str) -> String {
+        pgn.split("\n\n")
+            .nth(1)
+            .unwrap()
+            .trim_end()
+            .replace('\n', " ")
+    }
+
+    #[test]
+    fn write_seven_tag_roster() {
+        let pgn = write_pgn(&game(Position::starting(), &["e4", "e5"]));
+        assert_eq!(
+            tag_lines(&pgn),
+            [
+                "[Event \"?\"]",
+                "[Site \"?\"]",
+                "[Date \"????.??.??\"]",
+                "[Round \"?\"]",
+                "[White \"?\"]",
+                "[Black \"?\"]",
+                "[Result \"*\"]",
+            ]
+        );
+        assert_eq!(movetext(&pgn), "1. e4 e5 *");
+    }
+
+    #[test]
+    fn write_tags_in_order() {
+        let mut game = game(Position::starting(), &["e4"]);
+        game.tags = vec![
+            Tag::new("Annotator", "someone"),
+            Tag::new("White", "Morphy, \"Paul\""),
+            Tag::new("Result", "1-0"),
+            // the start position decides these
+            Tag::new("SetUp", "1"),
+            Tag::new("FEN", "8/8/8/8/8/8/8/8 w - - 0 1"),
+            Tag::new("Event", "Paris"),
+        ];
+        let pgn = write_pgn(&game);
+        assert_eq!(
+            tag_lines(&pgn),
+            [
+                "[Event \"Paris\"]",
+                "[Site \"?\"]",
+                "[Date \"????.??.??\"]",
+                "[Round \"?\"]",
+                "[White \"Morphy, \\\"Paul\\\"\"]",
+                "[Black \"?\"]",
+                "[Result \"1-0\"]",
+                "[Annotator \"someone\"]",
+            ]
+        );
+        // the result tag ends the moves too
+        assert_eq!(movetext(&pgn), "1. e4 1-0");
+    }
+
+    #[test]
+    fn write_setup_for_other_starts() {
+        let fen = "4k3/8/8/8/8/8/4P3/4K3 b - - 0 30";
+        let pgn = write_pgn(&game(Position::from_fen(fen).unwrap(), &["Kd7", "e4"]));
+        assert_eq!(
+            &tag_lines(&pgn)[7..],
+            ["[SetUp \"1\"]", &format!("[FEN \"{}\"]", fen)]
+        );
+        assert_eq!(movetext(&pgn), "30... Kd7 31. e4 *");
+    }
+
+    #[test]
+    fn write_variations_and_comments() {
+        let mut game = game(Position::starting(), &["e4", "e5", "Nf3"]);
+        let d4 = Position::starting().parse_san("d4").unwrap();
+        // a variation on white's first move, then black's reply to it
+        let e4 = game.position_at(&[0]).unwrap();
+        let c5 = e4.parse_san("c5").unwrap();
+        game.add_move(&[0], &c5);
+        game.add_move(&[], &d4);
+        game.node_mut(&[0]).unwrap().nags.push(1);
+        game.node_mut(&[0, 0]).unwrap().comment = Some("the classical reply".to_string());
+
+        assert_eq!(
+            movetext(&write_pgn(&game)),
+            "1. e4 $1 (1. d4) 1... e5 {the classical reply} (1... c5) 2. Nf3 *"
+        );
+    }
+
+    #[test]
+    fn write_wraps_lines() {
+        let mut game = game(Position::starting(), &OPERA_GAME);
+        game.tags = vec![Tag::new("Result", "1-0")];
+
+        // a comment of every length shifts where the lines break
+        for length in 1..LINE_WIDTH {
+            game.node_mut(&[0; 12]).unwrap().comment = Some(format!(
+                "an early queen sortie hitting b7 and f7 at once {}",
+                "x".repeat(length)
+            ));
+
+            let pgn = write_pgn(&game);
+            let moves: Vec<&str> = pgn.split("\n\n").nth(1).unwrap().lines().collect();
+            assert!(moves.len() > 2);
+            for line in moves {
+                assert!(line.chars().count() <= LINE_WIDTH, "{}", line);
+                // a move number is never left at the end of a line without its move
+                assert!(!line.ends_with('.'), "{}", line);
+            }
+            assert!(pgn.ends_with("17. Rd8# 1-0\n"));
+        }
+    }
+
+    // the moves along the main line from `path`
+    fn main_line(game: &Game, path: &[usize]) -> Vec<String> {
+        let line = game.mainline_from(path);
+        (path.len() + 1..=line.len())
+            .map(|length| game.node(&line[..length]).unwrap().san.clone())
+            .collect()
+    }
+
+    fn read_one(text: &str) -> Game {
+        let mut games = read_pgn(text);
+        assert_eq!(games.len(), 1, "{:?}", games);
+        games.remove(0).unwrap()
+    }
+
+    #[test]
+    fn read_several_games() {
+        let games = read_pgn(
+            "[Event \"one\"]\n[Result \"1-0\"]\n\n1. e4 e5 2. Qh5 1-0\n\n\
+             [Event \"two\"]\n\n1. d4 d5 *\n\n\
+             1. c4 0-1",
+        );
+        let games: Vec<Game> = games.into_iter().map(Result::unwrap).collect();
+        assert_eq!(games.len(), 3);
+        assert_eq!(games[0].tag("Event"), Some("one"));
+        assert_eq!(games[0].tag("Result"), Some("1-0"));
+        assert_eq!(main_line(&games[0], &[]), ["e4", "e5", "Qh5"]);
+        assert_eq!(games[1].tag("Event"), Some("two"));
+        // the result's picked up from the moves when there's no tag for it
+        assert_eq!(games[1].tag("Result"), Some("*"));
+        assert_eq!(main_line(&games[1], &[]), ["d4", "d5"]);
+        assert_eq!(games[2].tags, vec![Tag::new("Result", "0-1")]);
+        assert_eq!(main_line(&games[2], &[]), ["c4"]);
+    }
+
+    #[test]
+    fn read_from_a_fen_tag() {
+        let game = read_one(
+            "[SetUp \"1\"]\n[FEN \"4k3/8/8/8/8/8/4P3/4K3 b - - 0 30\"]\n\n30... Kd7 31. e4 *",
+        );
+        assert_eq!(game.start.to_fen(), "4k3/8/8/8/8/8/4P3/4K3 b - - 0 30");
+        assert_eq!(main_line(&game, &[]), ["Kd7", "e4"]);
+    }
+
+    #[test]
+    fn read_comments() {
+        let game = read_one(
+            "%an escaped line, it's for other programs\n\
+             {before anything} 1. e4 {the king's pawn} ; to the end of the line\n\
+             e5 {one} {two} 2. Nf3 (2. f4 {the gambit}) *",
+        );
+        let e4 = game.node(&[0]).unwrap();
+        assert_eq!(e4.before.as_deref(), Some("before anything"));
+        assert_eq!(
+            e4.comment.as_deref(),
+            Some("the king's pawn to the end of the line")
+        );
+        assert_eq!(
+            game.node(&[0, 0]).unwrap().comment.as_deref(),
+            Some("one two")
+        );
+        assert_eq!(
+            game.node(&[0, 0, 1]).unwrap().comment.as_deref(),
+            Some("the gambit")
+        );
+        assert_eq!(game.comment, None);
+
+        // with no moves the comment belongs to the game
+        let empty = read_one("{nothing played} *");
+        assert_eq!(empty.comment.as_deref(), Some("nothing played"));
+        assert!(empty.children.is_empty());
+    }
+
+    #[test]
+    fn read_nags() {
+        let game = read_one("1. e4! e5?! 2. Qh5 $2 $14 Nc6 3. Bc4 Nf6?? 4. Qxf7# 1-0");
+        assert_eq!(game.node(&[0]).unwrap().nags, [1]);
+        assert_eq!(game.node(&[0, 0]).unwrap().nags, [6]);
+        assert_eq!(game.node(&[0, 0, 0]).unwrap().nags, [2, 14]);
+        assert!(game.node(&[0, 0, 0, 0]).unwrap().nags.is_empty());
+        assert_eq!(game.node(&[0; 6]).unwrap().nags, [4]);
+        // the marks come off the move itself
+        assert_eq!(game.node(&[0; 6]).unwrap().san, "Nf6");
+    }
+
+    #[test]
+    fn read_nested_variations() {
+        let game = read_one("1. e4 e5 (1... c5 2. Nf3 (2. Nc3 Nc6) d6) (1... e6) 2. Nf3 *");
+        assert_eq!(main_line(&game, &[]), ["e4", "e5", "Nf3"]);
+        let replies: Vec<&str> = game
+            .children(&[0])
+            .unwrap()
+            .iter()
+            .map(|node| node.san.as_str())
+            .collect();
+        assert_eq!(replies, ["e5", "c5", "e6"]);
+        assert_eq!(main_line(&game, &[0, 1]), ["Nf3", "d6"]);
+        assert_eq!(main_line(&game, &[0, 1, 1]), ["Nc6"]);
+        assert_eq!(game.node(&[0, 1, 1]).unwrap().san, "Nc3");
+        assert_eq!(main_line(&game, &[0, 2]), Vec::<String>::new());
+    }
+
+    #[test]
+    fn read_recovers_from_bad_games() {
+        let games = read_pgn(
+            "[Event \"illegal\"]\n\n1. e4 e5 2. Ke3 Nc6 *\n\n\
+             [Event \"unclosed variation\"]\n\n1. e4 (1. d4 d5 *\n\n\
+             [Event \"stray bracket\"]\n\n1. e4 ) e5 *\n\n\
+             [Event \"fine\"]\n\n1. d4 *\n\n\
+             [Event \"unclosed comment\"]\n\n1. e4 {and then",
+        );
+        assert_eq!(games.len(), 5);
+        assert_eq!(
+            games[0],
+            Err(PgnError::IllegalMove {
+                number: 2,
+                turn: Turn::White,
+                san: "Ke3".to_string()
+            })
+        );
+        assert_eq!(games[1], Err(PgnError::Unclosed("variation")));
+        assert_eq!(games[2], Err(PgnError::MisplacedVariation));
+        let fine = games[3].as_ref().unwrap();
+        assert_eq!(fine.tag("Event"), Some("fine"));
+        assert_eq!(main_line(fine, &[]), ["d4"]);
+        assert_eq!(games[4], Err(PgnError::Unclosed("comment")));
+    }
+
+    #[test]
+    fn write_then_read() {
+        let mut opera = game(Position::starting(), &OPERA_GAME);
+        opera.tags = seven_tag_roster();
+        opera.tags[0] = Tag::new("Event", "Paris \"Opera\"");
+        opera.tags[6] = Tag::new("Result", "1-0");
+        opera.tags.push(Tag::new("Annotator", "someone"));
+
+        let e4 = opera.position_at(&[0]).unwrap();
+        let c5 = opera.add_move(&[0], &e4.parse_san("c5").unwrap()).unwrap();
+        let after_c5 = opera.position_at(&c5).unwrap();
+        opera.add_move(&c5, &after_c5.parse_san("Nf3").unwrap());
+        let c3 = opera
+            .add_move(&c5, &after_c5.parse_san("c3").unwrap())
+            .unwrap();
+        opera.node_mut(&c5).unwrap().before = Some("or the sicilian".to_string());
+        opera.node_mut(&c3).unwrap().comment = Some("the alapin".to_string());
+        opera.node_mut(&[0, 0, 0]).unwrap().nags = vec![1, 14];
+        opera.node_mut(&[0; 33]).unwrap().comment = Some("mate".to_string());
+
+        assert_eq!(read_one(&write_pgn(&opera)), opera);
+
+        // from somewhere else, with black to move
+        let from_fen = game(
+            Position::from_fen("4k3/8/8/8/8/8/4P3/4K3 b - - 0 30").unwrap(),
+            &["Kd7", "e4", "Ke6"],
+        );
+        let read = read_one(&write_pgn(&from_fen));
+        assert_eq!(read.start, from_fen.start);
+        assert_eq!(read.children, from_fen.children);
+    }
+}
@@ -1,8 +1,10 @@
 use crate::{
     assets::BoardAssets,
-    board::get_square,
+    board::BoardLayout,
     camera::ChessCamera,
-    types::{Board, WithActivePiece, WithSelectedPiece, WithSourcePiece},
+    fen::LoadFen,
+    play::{legal_move, Mode, PlayMove, Promotion},
+    types::{WithActivePiece, WithSelectedPiece, WithSourcePiece},
 };
 use bevy::{
     prelude::*,
@@ -11,73 +13,8 @@ use bevy::{
     ui::FocusPolicy,
 };
 use bevy_mod_picking::*;
-
-#[derive(Debug, Clone)]
-pub enum Side {
-    White(Kind),
-    Black(Kind),
-}
-
-#[derive(Debug, Clone, Default)]
-pub enum Kind {
-    #[default]
-    Pawn,
-    Rook,
-    Knight,
-    Bishop,
-    Queen,
-    King,
-}
-
-impl Default for Side {
-    fn default() -> Self {
-        Side::Black(Kind::default())
-    }
-}
-
-impl Side {
-    pub fn fen_str(&self) -> &str {
-        match self {
-            Self::White(p) => match p {
-                Kind::Queen => "Q",
-                Kind::King => "K",
-                Kind::Bishop => "B",
-                Kind::Knight => "N",
-                Kind::Rook => "R",
-                Kind::Pawn => "P",
-            },
-            Self::Black(p) => match p {
-                Kind::Queen => "q",
-                Kind::King => "k",
-                Kind::Bishop => "b",
-                Kind::Knight => "n",
-                Kind::Rook => "r",
-                Kind::Pawn => "p",
-            },
-        }
-    }
-
-    pub fn material_handle(&self, handles: PieceMaterialHandles) -> Handle<ColorMaterial> {
-        match self {
-            Self::White(p) => match p {
-                Kind::Queen => handles.wq_material_handle,
-                Kind::King => handles.wk_material_handle,
-                Kind::Bishop => handles.wb_material_handle,
-                Kind::Knight => handles.wn_material_handle,
-                Kind::Rook => handles.wr_material_handle,
-                Kind::Pawn => handles.wp_material_handle,
-            },
-            Self::Black(p) => match p {
-                Kind::Queen => handles.bq_material_handle,
-                Kind::King => handles.bk_material_handle,
-                Kind::Bishop => handles.bb_material_handle,
-                Kind::Knight => handles.bn_material_handle,
-                Kind::Rook => handles.br_material_handle,
-                Kind::Pawn => handles.bp_material_handle,
-            },
-        }
-    }
-}
+use chessbored::position::{en_passant_target, Position, STARTING_FEN};
+pub use chessbored::position::{Kind, Side};
 
 #[derive(Component, Debug, Clone, Default)]
 pub struct Piece {
@@ -104,6 +41,29 @@ pub struct PieceMaterialHandles {
     pub wp_material_handle: Handle<ColorMaterial>,
 }
 
+impl PieceMaterialHandles {
+    pub fn for_side(&self, side: &Side) -> Handle<ColorMaterial> {
+        match side {
+            Side::White(p) => match p {
+                Kind::Queen => self.wq_material_handle.clone(),
+                Kind::King => self.wk_material_handle.clone(),
+                Kind::Bishop => self.wb_material_handle.clone(),
+                Kind::Knight => self.wn_material_handle.clone(),
+                Kind::Rook => self.wr_material_handle.clone(),
+                Kind::Pawn => self.wp_material_handle.clone(),
+            },
+            Side::Black(p) => match p {
+                Kind::Queen => self.bq_material_handle.clone(),
+                Kind::King => self.bk_material_handle.clone(),
+                Kind::Bishop => self.bb_material_handle.clone(),
+                Kind::Knight => self.bn_material_handle.clone(),
+                Kind::Rook => self.br_material_handle.clone(),
+                Kind::Pawn => self.bp_material_handle.clone(),
+            },
+        }
+    }
+}
+
 #[derive(Component, Debug, Default)]
 pub struct ActivePiece;
 
@@ -125,7 +85,13 @@ pub fn side_piece_selection(
     )>,
     selected_query: Query<(&Piece, With<PickableMesh>, WithSelectedPiece)>,
     mouse_button_input: Res<Input<MouseButton>>,
+    mode: Res<Mode>,
 ) {
+    if *mode == Mode::Play {
+        // nothing new comes onto the board mid game
+        return;
+    }
+
     for event in events.iter() {
         if let (PickingEvent::Clicked(e), true) =
             (event, mouse_button_input.pressed(MouseButton::Left))
@@ -174,6 +140,8 @@ pub fn selection(
         WithActivePiece,
     )>,
     mouse_button_input: Res<Input<MouseButton>>,
+    mode: Res<Mode>,
+    position: Res<Position>,
 ) {
     for event in events.iter() {
         // picking up
@@ -183,6 +151,11 @@ pub fn selection(
             if let Ok((mut active_piece, active_transform, active_mesh, _, _)) =
                 active_query.get_mut(*e)
             {
+                if *mode == Mode::Play && active_piece.def.turn() != position.turn {
+                    // only the side to move gets to touch its pieces
+                    continue;
+                }
+
                 // there's no piece in hand so put the current selection in hand
                 commands
                     .spawn_bundle(MaterialMesh2dBundle {
@@ -213,7 +186,8 @@ pub fn selection(
 }
 
 pub fn drop_piece(
-    board: Res<Board>,
+    layout: Res<BoardLayout>,
+    mut position: ResMut<Position>,
     mut commands: Commands,
     mut active_query: Query<(
         &mut Piece,
@@ -230,42 +204,47 @@ pub fn drop_piece(
         WithSelectedPiece,
     )>,
     mouse_button_input: Res<Input<MouseButton>>,
+    mode: Res<Mode>,
+    promotion: Res<Promotion>,
+    mut play_events: EventWriter<PlayMove>,
 ) {
     if mouse_button_input.just_released(MouseButton::Left) {
         if let Ok((mut selected_piece, selected_transform, selected_mesh, _, _)) =
             selected_query.get_single_mut()
         {
-            if selected_transform.translation.x > 360.0 || selected_transform.translation.y < -10.0
-            {
+            let selected_pos = selected_transform.translation.truncate();
+            if !layout.can_drop(selected_pos) {
                 // don't allow placing on the right side of the board where the piece selections are
                 return;
             }
 
-            let (updated_x, updated_y) = if let Some(square) = get_square(
-                selected_transform.translation.x,
-                selected_transform.translation.y,
-            ) {
-                (
-                    board.get(&*square.to_string()).unwrap().x,
-                    board.get(&*square.to_string()).unwrap().y,
-                )
-            } else {
-                (
-                    selected_transform.translation.x,
-                    selected_transform.translation.y,
-                )
+            let selected_square = layout.square_at(selected_pos);
+            let from_square = selected_piece
+                .selected_translation
+                .and_then(|t| layout.square_at(t.truncate()));
+
+            if *mode == Mode::Play {
+                let mv = match (&from_square, &selected_square) {
+                    (Some(from), Some(to)) => legal_move(&position, from, to, &promotion),
+                    _ => None,
+                };
+                match mv {
+                    // the board's redrawn from the position once it's played
+                    Some(mv) => play_events.send(PlayMove(mv)),
+                    // not a move, so it goes back where it came from
+                    None => put_back(&selected_piece, selected_mesh, &mut commands),
+                }
+                selected_piece.stale = true;
+                return;
+            }
+
+            let (updated_x, updated_y) = match &selected_square {
+                Some(square) => layout.square_center(square).into(),
+                None => selected_pos.into(),
             };
 
             for (mut active_piece, active_transform, _, _, _) in active_query.iter_mut() {
-                let selected_square = get_square(
-                    selected_transform.translation.x,
-                    selected_transform.translation.y,
-                );
-
-                let active_square = get_square(
-                    active_transform.translation.x,
-                    active_transform.translation.y,
-                );
+                let active_square = layout.square_at(active_transform.translation.truncate());
 
                 if selected_square.is_some() && active_square == selected_square {
                     // placing on occupied space
@@ -273,6 +252,20 @@ pub fn drop_piece(
                 }
             }
 
+            let to_square = selected_square;
+            if from_square != to_square {
+                // a pawn's double push leaves an en passant target behind it and
+                // hands the move to the other side, any other move clears it
+                position.en_passant = None;
+                if let (Some(from), Some(to)) = (from_square, to_square) {
+                    if let Some((target, turn)) = en_passant_target(&selected_piece.def, &from, &to)
+                    {
+                        position.en_passant = Some(target);
+                        position.turn = turn;
+                    }
+                }
+            }
+
             commands
                 .spawn_bundle(MaterialMesh2dBundle {
                     mesh: selected_mesh.clone(),
@@ -335,51 +328,59 @@ pub fn cancel_piece_movement(
         WithSelectedPiece,
     )>,
     keys: Res<Input<KeyCode>>,
+    mode: Res<Mode>,
 ) {
+    let remove = keys.pressed(KeyCode::X)
+        && !(keys.pressed(KeyCode::LShift) || keys.pressed(KeyCode::RShift));
+
     for (entity, piece, mesh, _, _) in query.iter_mut() {
-        if keys.pressed(KeyCode::Escape) {
-            if let Some(selected_translation) = piece.selected_translation {
-                commands
-                    .spawn_bundle(MaterialMesh2dBundle {
-                        mesh: mesh.clone(),
-                        transform: Transform::from_translation(selected_translation),
-                        material: piece.sprite_handle.clone(),
-                        ..default()
-                    })
-                    .insert_bundle(PickableBundle {
-                        focus_policy: FocusPolicy::Pass,
-                        ..default()
-                    })
-                    .insert(Piece {
-                        def: piece.def.clone(),
-                        selected_translation: Some(selected_translation),
-                        sprite_handle: piece.sprite_handle.clone(),
-                        ..default()
-                    })
-                    .insert(ActivePiece);
-                commands.entity(entity).despawn_recursive();
-            } else {
-                commands.entity(entity).despawn_recursive();
-            }
-        } else if keys.pressed(KeyCode::X)
-            && !(keys.pressed(KeyCode::LShift) || keys.pressed(KeyCode::RShift))
-        {
+        // pieces can't be taken off the board mid game, only put back
+        if keys.pressed(KeyCode::Escape) || (remove && *mode == Mode::Play) {
+            put_back(&piece, mesh, &mut commands);
+            commands.entity(entity).despawn_recursive();
+        } else if remove {
             commands.entity(entity).despawn_recursive();
         }
     }
 }
 
+/// a copy of the piece in hand back where it was picked up from
+fn put_back(piece: &Piece, mesh: &Mesh2dHandle, commands: &mut Commands) {
+    if let Some(selected_translation) = piece.selected_translation {
+        commands
+            .spawn_bundle(MaterialMesh2dBundle {
+                mesh: mesh.clone(),
+                transform: Transform::from_translation(selected_translation),
+                material: piece.sprite_handle.clone(),
+                ..default()
+            })
+            .insert_bundle(PickableBundle {
+                focus_policy: FocusPolicy::Pass,
+                ..default()
+            })
+            .insert(Piece {
+                def: piece.def.clone(),
+                selected_translation: Some(selected_translation),
+                sprite_handle: piece.sprite_handle.clone(),
+                ..default()
+            })
+            .insert(ActivePiece);
+    }
+}
+
 pub fn clear_board(
     mut commands: Commands,
     mut active_query: Query<(Entity, &Piece, With<PickableMesh>, WithActivePiece)>,
     mut selected_query: Query<(Entity, &Piece, With<PickableMesh>, WithSelectedPiece)>,
     keys: Res<Input<KeyCode>>,
+    mode: Res<Mode>,
 ) {
+    let clear = *mode == Mode::Setup
+        && keys.pressed(KeyCode::X)
+        && (keys.pressed(KeyCode::LShift) || keys.pressed(KeyCode::RShift));
+
     for (entity, piece, _, _) in active_query.iter_mut() {
-        if (keys.pressed(KeyCode::X)
-            && (keys.pressed(KeyCode::LShift) || keys.pressed(KeyCode::RShift)))
-            || piece.stale
-        {
+        if clear || piece.stale {
             commands.entity(entity).despawn_recursive();
         }
     }
@@ -413,11 +414,11 @@ pub fn set_sprite_handles(
 }
 
 pub fn place_piece<C: Component>(
-    pos: &str,
+    at: Vec2,
     side: Side,
     piece_kind: C,
     material_handle: &Handle<ColorMaterial>,
-    board: &Res<Board>,
+    layout: &BoardLayout,
     commands: &mut Commands,
     meshes: &mut ResMut<Assets<Mesh>>,
 ) {
@@ -426,15 +427,11 @@ pub fn place_piece<C: Component>(
         .spawn_bundle(MaterialMesh2dBundle {
             mesh: meshes
                 .add(Mesh::from(shape::Quad {
-                    size: Vec2::new(50.0, 50.0),
+                    size: Vec2::splat(layout.square_size),
                     ..default()
                 }))
                 .into(),
-            transform: Transform::from_xyz(
-                board.get(pos).unwrap().x,
-                board.get(pos).unwrap().y,
-                pz,
-            ),
+            transform: Transform::from_xyz(at.x, at.y, pz),
             material: material_handle.clone(),
             ..default()
         })
@@ -445,458 +442,43 @@ pub fn place_piece<C: Component>(
         .insert(Piece {
             def: side,
             sprite_handle: material_handle.clone(),
-            selected_translation: Some(Vec3::new(
-                board.get(pos).unwrap().x,
-                board.get(pos).unwrap().y,
-                pz,
-            )),
+            selected_translation: Some(at.extend(pz)),
             ..default()
         })
         .insert(piece_kind);
 }
 
 pub fn setup_piece_selection(
-    board: Res<Board>,
+    layout: Res<BoardLayout>,
     piece_material_handles: Res<PieceMaterialHandles>,
     mut commands: Commands,
     mut meshes: ResMut<Assets<Mesh>>,
 ) {
-    // row 1 black
-    place_piece(
-        "bq",
-        Side::Black(Kind::Queen),
-        SourcePiece,
-        &piece_material_handles.bq_material_handle,
-        &board,
-        &mut commands,
-        &mut meshes,
-    );
-    place_piece(
-        "bk",
-        Side::Black(Kind::King),
-        SourcePiece,
-        &piece_material_handles.bk_material_handle,
-        &board,
-        &mut commands,
-        &mut meshes,
-    );
-    // row 2 black
-    place_piece(
-        "bp",
-        Side::Black(Kind::Pawn),
-        SourcePiece,
-        &piece_material_handles.bp_material_handle,
-        &board,
-        &mut commands,
-        &mut meshes,
-    );
-    place_piece(
-        "bb",
-        Side::Black(Kind::Bishop),
-        SourcePiece,
-        &piece_material_handles.bb_material_handle,
-        &board,
-        &mut commands,
-        &mut meshes,
-    );
-    // row 3 black
-    place_piece(
-        "bn",
-        Side::Black(Kind::Knight),
-        SourcePiece,
-        &piece_material_handles.bn_material_handle,
-        &board,
-        &mut commands,
-        &mut meshes,
-    );
-    place_piece(
-        "br",
-        Side::Black(Kind::Rook),
-        SourcePiece,
-        &piece_material_handles.br_material_handle,
-        &board,
-        &mut commands,
-        &mut meshes,
-    );
-
-    // row 1 white
-    place_piece(
-        "wq",
-        Side::White(Kind::Queen),
-        SourcePiece,
-        &piece_material_handles.wq_material_handle,
-        &board,
-        &mut commands,
-        &mut meshes,
-    );
-    place_piece(
-        "wk",
-        Side::White(Kind::King),
-        SourcePiece,
-        &piece_material_handles.wk_material_handle,
-        &board,
-        &mut commands,
-        &mut meshes,
-    );
-    // row 2 white
-    place_piece(
-        "wp",
-        Side::White(Kind::Pawn),
-        SourcePiece,
-        &piece_material_handles.wp_material_handle,
-        &board,
-        &mut commands,
-        &mut meshes,
-    );
-    place_piece(
-        "wb",
-        Side::White(Kind::Bishop),
-        SourcePiece,
-        &piece_material_handles.wb_material_handle,
-        &board,
-        &mut commands,
-        &mut meshes,
-    );
-    // row 3 white
-    place_piece(
-        "wn",
-        Side::White(Kind::Knight),
-        SourcePiece,
-        &piece_material_handles.wn_material_handle,
-        &board,
-        &mut commands,
-        &mut meshes,
-    );
-    place_piece(
-        "wr",
-        Side::White(Kind::Rook),
-        SourcePiece,
-        &piece_material_handles.wr_material_handle,
-        &board,
-        &mut commands,
-        &mut meshes,
-    );
-}
-
-pub fn starting_positions(
-    board: Res<Board>,
-    mut commands: Commands,
-    piece_material_handles: Res<PieceMaterialHandles>,
-    mut meshes: ResMut<Assets<Mesh>>,
-    query: Query<(Entity, &Piece, With<PickableMesh>, WithActivePiece)>,
-    keys: Res<Input<KeyCode>>,
-) {
-    if !keys.pressed(KeyCode::I) {
-        return;
+    let kinds = [
+        Kind::Queen,
+        Kind::King,
+        Kind::Pawn,
+        Kind::Bishop,
+        Kind::Knight,
+        Kind::Rook,
+    ];
+    for kind in kinds {
+        for side in [Side::Black(kind.clone()), Side::White(kind.clone())] {
+            place_piece(
+                layout.palette_position(&side),
+                side.clone(),
+                SourcePiece,
+                &piece_material_handles.for_side(&side),
+                &layout,
+                &mut commands,
+                &mut meshes,
+            );
+        }
     }
+}
 
-    for (entity, _piece, _, _) in query.iter() {
-        commands.entity(entity).despawn_recursive();
+pub fn starting_positions(mut load_events: EventWriter<LoadFen>, keys: Res<Input<KeyCode>>) {
+    if keys.just_pressed(KeyCode::I) {
+        load_events.send(LoadFen(STARTING_FEN.to_string()));
     }
-
-    // black pawns
-    place_piece(
-        "a7",
-        Side::Black(Kind::Pawn),
-        ActivePiece,
-        &piece_material_handles.bp_material_handle,
-        &board,
-        &mut commands,
-        &mut meshes,
-    );
-    place_piece(
-        "b7",
-        Side::Black(Kind::Pawn),
-        ActivePiece,
-        &piece_material_handles.bp_material_handle,
-        &board,
-        &mut commands,
-        &mut meshes,
-    );
-    place_piece(
-        "c7",
-        Side::Black(Kind::Pawn),
-        ActivePiece,
-        &piece_material_handles.bp_material_handle,
-        &board,
-        &mut commands,
-        &mut meshes,
-    );
-    place_piece(
-        "d7",
-        Side::Black(Kind::Pawn),
-        ActivePiece,
-        &piece_material_handles.bp_material_handle,
-        &board,
-        &mut commands,
-        &mut meshes,
-    );
-    place_piece(
-        "e7",
-        Side::Black(Kind::Pawn),
-        ActivePiece,
-        &piece_material_handles.bp_material_handle,
-        &board,
-        &mut commands,
-        &mut meshes,
-    );
-    place_piece(
-        "f7",
-        Side::Black(Kind::Pawn),
-        ActivePiece,
-        &piece_material_handles.bp_material_handle,
-        &board,
-        &mut commands,
-        &mut meshes,
-    );
-    place_piece(
-        "g7",
-        Side::Black(Kind::Pawn),
-        ActivePiece,
-        &piece_material_handles.bp_material_handle,
-        &board,
-        &mut commands,
-        &mut meshes,
-    );
-    place_piece(
-        "h7",
-        Side::Black(Kind::Pawn),
-        ActivePiece,
-        &piece_material_handles.bp_material_handle,
-        &board,
-        &mut commands,
-        &mut meshes,
-    );
-
-    // black major/minor
-    place_piece(
-        "a8",
-        Side::Black(Kind::Rook),
-        ActivePiece,
-        &piece_material_handles.br_material_handle,
-        &board,
-        &mut commands,
-        &mut meshes,
-    );
-    place_piece(
-        "b8",
-        Side::Black(Kind::Knight),
-        ActivePiece,
-        &piece_material_handles.bn_material_handle,
-        &board,
-        &mut commands,
-        &mut meshes,
-    );
-    place_piece(
-        "c8",
-        Side::Black(Kind::Bishop),
-        ActivePiece,
-        &piece_material_handles.bb_material_handle,
-        &board,
-        &mut commands,
-        &mut meshes,
-    );
-
-    place_piece(
-        "d8",
-        Side::Black(Kind::Queen),
-        ActivePiece,
-        &piece_material_handles.bq_material_handle,
-        &board,
-        &mut commands,
-        &mut meshes,
-    );
-
-    place_piece(
-        "e8",
-        Side::Black(Kind::King),
-        ActivePiece,
-        &piece_material_handles.bk_material_handle,
-        &board,
-        &mut commands,
-        &mut meshes,
-    );
-
-    place_piece(
-        "f8",
-        Side::Black(Kind::Bishop),
-        ActivePiece,
-        &piece_material_handles.bb_material_handle,
-        &board,
-        &mut commands,
-        &mut meshes,
-    );
-
-    place_piece(
-        "g8",
-        Side::Black(Kind::Knight),
-        ActivePiece,
-        &piece_material_handles.bn_material_handle,
-        &board,
-        &mut commands,
-        &mut meshes,
-    );
-
-    place_piece(
-        "h8",
-        Side::Black(Kind::Rook),
-        ActivePiece,
-        &piece_material_handles.br_material_handle,
-        &board,
-        &mut commands,
-        &mut meshes,
-    );
-
-    // white pawns
-    place_piece(
-        "a2",
-        Side::White(Kind::Pawn),
-        ActivePiece,
-        &piece_material_handles.wp_material_handle,
-        &board,
-        &mut commands,
-        &mut meshes,
-    );
-    place_piece(
-        "b2",
-        Side::White(Kind::Pawn),
-        ActivePiece,
-        &piece_material_handles.wp_material_handle,
-        &board,
-        &mut commands,
-        &mut meshes,
-    );
-    place_piece(
-        "c2",
-        Side::White(Kind::Pawn),
-        ActivePiece,
-        &piece_material_handles.wp_material_handle,
-        &board,
-        &mut commands,
-        &mut meshes,
-    );
-    place_piece(
-        "d2",
-        Side::White(Kind::Pawn),
-        ActivePiece,
-        &piece_material_handles.wp_material_handle,
-        &board,
-        &mut commands,
-        &mut meshes,
-    );
-    place_piece(
-        "e2",
-        Side::White(Kind::Pawn),
-        ActivePiece,
-        &piece_material_handles.wp_material_handle,
-        &board,
-        &mut commands,
-        &mut meshes,
-    );
-    place_piece(
-        "f2",
-        Side::White(Kind::Pawn),
-        ActivePiece,
-        &piece_material_handles.wp_material_handle,
-        &board,
-        &mut commands,
-        &mut meshes,
-    );
-    place_piece(
-        "g2",
-        Side::White(Kind::Pawn),
-        ActivePiece,
-        &piece_material_handles.wp_material_handle,
-        &board,
-        &mut commands,
-        &mut meshes,
-    );
-    place_piece(
-        "h2",
-        Side::White(Kind::Pawn),
-        ActivePiece,
-        &piece_material_handles.wp_material_handle,
-        &board,
-        &mut commands,
-        &mut meshes,
-    );
-
-    // white major/minor
-    place_piece(
-        "a1",
-        Side::White(Kind::Rook),
-        ActivePiece,
-        &piece_material_handles.wr_material_handle,
-        &board,
-        &mut commands,
-        &mut meshes,
-    );
-    place_piece(
-        "b1",
-        Side::White(Kind::Knight),
-        ActivePiece,
-        &piece_material_handles.wn_material_handle,
-        &board,
-        &mut commands,
-        &mut meshes,
-    );
-    place_piece(
-        "c1",
-        Side::White(Kind::Bishop),
-        ActivePiece,
-        &piece_material_handles.wb_material_handle,
-        &board,
-        &mut commands,
-        &mut meshes,
-    );
-
-    place_piece(
-        "d1",
-        Side::White(Kind::Queen),
-        ActivePiece,
-        &piece_material_handles.wq_material_handle,
-        &board,
-        &mut commands,
-        &mut meshes,
-    );
-
-    place_piece(
-        "e1",
-        Side::White(Kind::King),
-        ActivePiece,
-        &piece_material_handles.wk_material_handle,
-        &board,
-        &mut commands,
-        &mut meshes,
-    );
-
-    place_piece(
-        "f1",
-        Side::White(Kind::Bishop),
-        ActivePiece,
-        &piece_material_handles.wb_material_handle,
-        &board,
-        &mut commands,
-        &mut meshes,
-    );
-
-    place_piece(
-        "g1",
-        Side::White(Kind::Knight),
-        ActivePiece,
-        &piece_material_handles.wn_material_handle,
-        &board,
-        &mut commands,
-        &mut meshes,
-    );
-
-    place_piece(
-        "h1",
-        Side::White(Kind::Rook),
-        ActivePiece,
-        &piece_material_handles.wr_material_handle,
-        &board,
-        &mut commands,
-        &mut meshes,
-    );
 }
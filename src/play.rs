@@ -0,0 +1,247 @@
+use crate::{
+    board::{BoardLayout, Square},
+    fen::replace_board,
+    history::MoveHistory,
+    persist::Settings,
+    pieces::{Kind, Piece, PieceMaterialHandles, Side},
+    types::{WithActivePiece, WithSelectedPiece},
+};
+use bevy::{prelude::*, sprite::MaterialMesh2dBundle};
+use bevy_egui::egui;
+use bevy_mod_picking::PickableMesh;
+use chessbored::{moves::Move, position::Position};
+
+/// what moving pieces around does. setting up, anything goes and the board is the
+/// position. playing, only legal moves for the side to move are let through and the
+/// position decides what the board shows
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Mode {
+    Setup,
+    Play,
+}
+
+impl Default for Mode {
+    fn default() -> Self {
+        Mode::Setup
+    }
+}
+
+/// what a pawn reaching the last rank turns into while playing
+#[derive(Component, Debug, Clone, PartialEq, Eq)]
+pub struct Promotion(pub Kind);
+
+impl Default for Promotion {
+    fn default() -> Self {
+        Promotion(Kind::Queen)
+    }
+}
+
+/// the legal move that takes the piece on `from` to `to`, if there is one
+pub fn legal_move(
+    position: &Position,
+    from: &Square,
+    to: &Square,
+    promotion: &Promotion,
+) -> Option<Move> {
+    position
+        .legal_moves_from(from)
+        .into_iter()
+        .filter(|mv| mv.to == *to)
+        .find(|mv| mv.promotion.is_none() || mv.promotion.as_ref() == Some(&promotion.0))
+}
+
+/// a legal move dropped on the board, to be played once the frame's edits are in
+pub struct PlayMove(pub Move);
+
+pub fn toggle_mode(
+    keys: Res<Input<KeyCode>>,
+    mut mode: ResMut<Mode>,
+    selected_query: Query<&Piece, WithSelectedPiece>,
+) {
+    // the piece in hand means something different on each side of the switch
+    if keys.just_pressed(KeyCode::P) && selected_query.is_empty() {
+        *mode = match *mode {
+            Mode::Setup => Mode::Play,
+            Mode::Play => Mode::Setup,
+        };
+    }
+}
+
+/// make the move on the position and redraw the board from it, which takes care
+/// of captures, the castling rook, en passant and promotion all at once
+pub fn play_move(
+    mut play_events: EventReader<PlayMove>,
+    mode: Res<Mode>,
+    mut position: ResMut<Position>,
+    mut history: ResMut<MoveHistory>,
+    layout: Res<BoardLayout>,
+    piece_material_handles: Res<PieceMaterialHandles>,
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    query: Query<(Entity, &Piece, With<PickableMesh>, WithActivePiece)>,
+) {
+    let mut played = position.clone();
+    let mut moved = false;
+    for PlayMove(mv) in play_events.iter() {
+        // setting up doesn't make moves, they're read and dropped
+        if *mode != Mode::Play {
+            continue;
+        }
+        history.play(&played, mv);
+        played.play(mv);
+        moved = true;
+    }
+
+    if moved {
+        replace_board(
+            played,
+            &query,
+            &mut position,
+            &layout,
+            &piece_material_handles,
+            &mut commands,
+            &mut meshes,
+        );
+    }
+}
+
+pub fn toggle_move_hints(keys: Res<Input<KeyCode>>, mut settings: ResMut<Settings>) {
+    if keys.just_pressed(KeyCode::M) {
+        settings.show_setup_moves = !settings.show_setup_moves;
+    }
+}
+
+/// a dot or tint on a square the piece in hand could legally move to
+#[derive(Component)]
+pub struct MoveHint;
+
+/// mark everywhere the piece in hand can go, always while playing and when asked
+/// for while setting up
+pub fn mark_legal_moves(
+    mut commands: Commands,
+    layout: Res<BoardLayout>,
+    position: Res<Position>,
+    mode: Res<Mode>,
+    settings: Res<Settings>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+    selected_query: Query<&Piece, WithSelectedPiece>,
+    hint_query: Query<Entity, With<MoveHint>>,
+    mut marked_for: Local<Option<(Square, Side)>>,
+) {
+    let showing = *mode == Mode::Play || settings.show_setup_moves;
+    let held = selected_query
+        .get_single()
+        .ok()
+        .filter(|_| showing)
+        .and_then(|piece| {
+            let from = layout.square_at(piece.selected_translation?.truncate())?;
+            Some((from, piece.def.clone()))
+        });
+
+    if held == *marked_for && !position.is_changed() && !layout.is_changed() {
+        return;
+    }
+
+    for entity in hint_query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+    *marked_for = held.clone();
+
+    let (from, side) = match held {
+        Some(held) => held,
+        None => return,
+    };
+
+    // while setting up the piece in hand has already left the position, and it
+    // might not belong to the side to move
+    let mut moving = position.clone();
+    moving.put(from, side.clone());
+    if *mode == Mode::Setup {
+        moving.turn = side.turn();
+    }
+
+    let mut destinations: Vec<(Square, bool)> = vec![];
+    for mv in moving.legal_moves_from(&from) {
+        if destinations.iter().any(|(to, _)| *to == mv.to) {
+            // one per square, not one per promotion
+            continue;
+        }
+        let en_passant = *side.kind() == Kind::Pawn && mv.from.file != mv.to.file;
+        let capture = moving.piece_at(&mv.to).is_some() || en_passant;
+        destinations.push((mv.to, capture));
+    }
+
+    for (to, capture) in destinations {
+        let pos = layout.square_center(&to);
+        // above the squares and problem markers, below the pieces
+        let transform = Transform::from_xyz(pos.x, pos.y, -0.004);
+        if capture {
+            commands
+                .spawn_bundle(SpriteBundle {
+                    sprite: Sprite {
+                        color: Color::rgba(0.85, 0.45, 0.1, 0.45),
+                        custom_size: Some(Vec2::splat(layout.square_size)),
+                        ..default()
+                    },
+                    transform,
+                    ..default()
+                })
+                .insert(MoveHint);
+        } else {
+            commands
+                .spawn_bundle(MaterialMesh2dBundle {
+                    mesh: meshes
+                        .add(Mesh::from(shape::Circle::new(0.15 * layout.square_size)))
+                        .into(),
+                    material: materials.add(ColorMaterial::from(Color::rgba(0.1, 0.1, 0.1, 0.35))),
+                    transform,
+                    ..default()
+                })
+                .insert(MoveHint);
+        }
+    }
+}
+
+/// the mode switch and, while playing, the promotion choice, for the position panel
+pub fn mode_controls(
+    ui: &mut egui::Ui,
+    mode: &mut ResMut<Mode>,
+    promotion: &mut ResMut<Promotion>,
+    holding: bool,
+    text_color: egui::Color32,
+) {
+    let mut chosen_mode = **mode;
+    let mut chosen_promotion = promotion.0.clone();
+
+    ui.horizontal(|ui| {
+        ui.label(egui::RichText::new("mode:").monospace().color(text_color));
+        ui.add_enabled_ui(!holding, |ui| {
+            ui.selectable_value(&mut chosen_mode, Mode::Setup, "setup");
+            ui.selectable_value(&mut chosen_mode, Mode::Play, "play");
+        });
+        if chosen_mode == Mode::Play {
+            ui.label(
+                egui::RichText::new("promote to:")
+                    .monospace()
+                    .color(text_color),
+            );
+            for (kind, name) in [
+                (Kind::Queen, "queen"),
+                (Kind::Rook, "rook"),
+                (Kind::Bishop, "bishop"),
+                (Kind::Knight, "knight"),
+            ] {
+                ui.selectable_value(&mut chosen_promotion, kind, name);
+            }
+        }
+    });
+
+    // only touch the resources when something was picked so change detection means something
+    if chosen_mode != **mode {
+        **mode = chosen_mode;
+    }
+    if chosen_promotion != promotion.0 {
+        promotion.0 = chosen_promotion;
+    }
+}
@@ -0,0 +1,912 @@
+use crate::square::{File, Rank, Square};
+use std::{
+    fmt::{self, Write as _},
+    str::FromStr,
+};
+
+pub const STARTING_FEN: &str = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Turn {
+    White,
+    Black,
+}
+
+impl Turn {
+    pub fn fen_str(&self) -> &str {
+        match self {
+            Self::White => "w",
+            Self::Black => "b",
+        }
+    }
+
+    pub fn other(&self) -> Turn {
+        match self {
+            Self::White => Self::Black,
+            Self::Black => Self::White,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Side {
+    White(Kind),
+    Black(Kind),
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub enum Kind {
+    #[default]
+    Pawn,
+    Rook,
+    Knight,
+    Bishop,
+    Queen,
+    King,
+}
+
+impl Default for Side {
+    fn default() -> Self {
+        Side::Black(Kind::default())
+    }
+}
+
+impl Side {
+    pub fn fen_str(&self) -> &str {
+        match self {
+            Self::White(p) => match p {
+                Kind::Queen => "Q",
+                Kind::King => "K",
+                Kind::Bishop => "B",
+                Kind::Knight => "N",
+                Kind::Rook => "R",
+                Kind::Pawn => "P",
+            },
+            Self::Black(p) => match p {
+                Kind::Queen => "q",
+                Kind::King => "k",
+                Kind::Bishop => "b",
+                Kind::Knight => "n",
+                Kind::Rook => "r",
+                Kind::Pawn => "p",
+            },
+        }
+    }
+
+    pub fn from_fen_char(piece: char) -> Option<Side> {
+        match piece {
+            'q' => Some(Side::Black(Kind::Queen)),
+            'k' => Some(Side::Black(Kind::King)),
+            'b' => Some(Side::Black(Kind::Bishop)),
+            'n' => Some(Side::Black(Kind::Knight)),
+            'r' => Some(Side::Black(Kind::Rook)),
+            'p' => Some(Side::Black(Kind::Pawn)),
+            'Q' => Some(Side::White(Kind::Queen)),
+            'K' => Some(Side::White(Kind::King)),
+            'B' => Some(Side::White(Kind::Bishop)),
+            'N' => Some(Side::White(Kind::Knight)),
+            'R' => Some(Side::White(Kind::Rook)),
+            'P' => Some(Side::White(Kind::Pawn)),
+            _ => None,
+        }
+    }
+
+    /// the colour this piece plays for
+    pub fn turn(&self) -> Turn {
+        match self {
+            Self::White(_) => Turn::White,
+            Self::Black(_) => Turn::Black,
+        }
+    }
+
+    pub fn kind(&self) -> &Kind {
+        match self {
+            Self::White(kind) | Self::Black(kind) => kind,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CastlingRights {
+    pub white_kingside: bool,
+    pub white_queenside: bool,
+    pub black_kingside: bool,
+    pub black_queenside: bool,
+}
+
+impl CastlingRights {
+    pub fn all() -> CastlingRights {
+        CastlingRights {
+            white_kingside: true,
+            white_queenside: true,
+            black_kingside: true,
+            black_queenside: true,
+        }
+    }
+
+    pub fn fen_str(&self) -> String {
+        let mut res = String::new();
+        if self.white_kingside {
+            res += "K";
+        }
+        if self.white_queenside {
+            res += "Q";
+        }
+        if self.black_kingside {
+            res += "k";
+        }
+        if self.black_queenside {
+            res += "q";
+        }
+        if res.is_empty() {
+            res += "-";
+        }
+        res
+    }
+
+    /// rights that are set in both `self` and `other`
+    pub fn and(&self, other: &CastlingRights) -> CastlingRights {
+        CastlingRights {
+            white_kingside: self.white_kingside && other.white_kingside,
+            white_queenside: self.white_queenside && other.white_queenside,
+            black_kingside: self.black_kingside && other.black_kingside,
+            black_queenside: self.black_queenside && other.black_queenside,
+        }
+    }
+}
+
+const EMPTY: Option<Side> = None;
+
+/// a chess position: what's on each square plus everything else a FEN record holds
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Position {
+    // indexed by `Square::index`
+    squares: [Option<Side>; 64],
+    pub turn: Turn,
+    // rights that are claimed, they only count while the king and rook are home,
+    // see `castling_available`
+    pub castling: CastlingRights,
+    pub en_passant: Option<Square>,
+    pub halfmove_clock: u32,
+    pub fullmove_number: u32,
+}
+
+impl Default for Position {
+    /// an empty board, white to move, with castling left to whatever the pieces allow
+    fn default() -> Self {
+        Position {
+            squares: [EMPTY; 64],
+            turn: Turn::White,
+            castling: CastlingRights::all(),
+            en_passant: None,
+            halfmove_clock: 0,
+            fullmove_number: 1,
+        }
+    }
+}
+
+impl Position {
+    pub fn starting() -> Position {
+        Position::from_fen(STARTING_FEN).expect("the starting FEN parses")
+    }
+
+    /// reads a FEN record, the fields after piece placement are optional and default to
+    /// white to move, no castling, no en passant target, and fresh move counters
+    pub fn from_fen(fen: &str) -> Result<Position, FenError> {
+        let fields = fields_with_index(fen);
+
+        let (placement_index, placement) = match fields.first() {
+            Some(field) => *field,
+            None => return Err(FenError::Empty),
+        };
+
+        if let Some((index, _)) = fields.get(6) {
+            return Err(FenError::TooManyFields { index: *index });
+        }
+
+        let mut position = Position {
+            castling: CastlingRights::default(),
+            ..Position::default()
+        };
+        for (square, side) in parse_placement(placement_index, placement)? {
+            position.put(square, side);
+        }
+
+        if let Some(&(index, turn)) = fields.get(1) {
+            position.turn = match turn {
+                "w" => Turn::White,
+                "b" => Turn::Black,
+                _ => return Err(FenError::InvalidSideToMove { index }),
+            };
+        }
+
+        if let Some(&(index, castling)) = fields.get(2) {
+            position.castling = parse_castling(index, castling)?;
+        }
+
+        if let Some(&(index, en_passant)) = fields.get(3) {
+            position.en_passant = match en_passant {
+                "-" => None,
+                s => match s.parse::<Square>() {
+                    Ok(square) if square.rank == Rank::Three || square.rank == Rank::Six => {
+                        Some(square)
+                    }
+                    _ => return Err(FenError::InvalidEnPassant { index }),
+                },
+            };
+        }
+
+        if let Some(&(index, halfmove_clock)) = fields.get(4) {
+            position.halfmove_clock = halfmove_clock
+                .parse()
+                .map_err(|_| FenError::InvalidHalfmoveClock { index })?;
+        }
+
+        if let Some(&(index, fullmove_number)) = fields.get(5) {
+            position.fullmove_number = match fullmove_number.parse() {
+                Ok(n) if n > 0 => n,
+                _ => return Err(FenError::InvalidFullmoveNumber { index }),
+            };
+        }
+
+        Ok(position)
+    }
+
+    /// the full six field FEN record, castling and en passant only list what the
+    /// pieces actually allow
+    pub fn to_fen(&self) -> String {
+        let en_passant = match self.valid_en_passant() {
+            Some(square) => square.to_string(),
+            None => "-".to_string(),
+        };
+
+        let placement: Vec<String> = Rank::ALL
+            .iter()
+            .rev()
+            .map(|rank| self.fen_for_rank(rank))
+            .collect();
+
+        format!(
+            "{} {} {} {} {} {}",
+            placement.join("/"),
+            self.turn.fen_str(),
+            self.castling_available().fen_str(),
+            en_passant,
+            self.halfmove_clock,
+            self.fullmove_number,
+        )
+    }
+
+    pub fn piece_at(&self, square: &Square) -> Option<&Side> {
+        self.squares[square.index()].as_ref()
+    }
+
+    /// puts `side` on `square`, handing back whatever was there
+    pub fn put(&mut self, square: Square, side: Side) -> Option<Side> {
+        self.squares[square.index()].replace(side)
+    }
+
+    pub fn remove(&mut self, square: &Square) -> Option<Side> {
+        self.squares[square.index()].take()
+    }
+
+    /// every occupied square, a1 through h1 then on up the board
+    pub fn pieces(&self) -> impl Iterator<Item = (Square, &Side)> {
+        self.squares
+            .iter()
+            .enumerate()
+            .filter_map(|(index, side)| Some((Square::from_index(index)?, side.as_ref()?)))
+    }
+
+    /// takes every piece off the board, leaving the other fields alone
+    pub fn clear(&mut self) {
+        self.squares = [EMPTY; 64];
+    }
+
+    /// the claimed castling rights that still have a king and rook on their home squares
+    pub fn castling_available(&self) -> CastlingRights {
+        let is = |file: File, rank: Rank, side: Side| {
+            self.piece_at(&Square::new(file, rank)) == Some(&side)
+        };
+
+        let white_king_home = is(File::E, Rank::One, Side::White(Kind::King));
+        let black_king_home = is(File::E, Rank::Eight, Side::Black(Kind::King));
+
+        self.castling.and(&CastlingRights {
+            white_kingside: white_king_home && is(File::H, Rank::One, Side::White(Kind::Rook)),
+            white_queenside: white_king_home && is(File::A, Rank::One, Side::White(Kind::Rook)),
+            black_kingside: black_king_home && is(File::H, Rank::Eight, Side::Black(Kind::Rook)),
+            black_queenside: black_king_home && is(File::A, Rank::Eight, Side::Black(Kind::Rook)),
+        })
+    }
+
+    /// the en passant target, if the pawn that just made a double push is still
+    /// sitting in front of it and the target square itself is empty
+    pub fn valid_en_passant(&self) -> Option<&Square> {
+        let square = self.en_passant.as_ref()?;
+        let (pawn_rank, pawn) = match (&square.rank, self.turn) {
+            (Rank::Three, Turn::Black) => (Rank::Four, Side::White(Kind::Pawn)),
+            (Rank::Six, Turn::White) => (Rank::Five, Side::Black(Kind::Pawn)),
+            _ => return None,
+        };
+
+        let pawn_square = Square::new(square.file, pawn_rank);
+        if self.piece_at(square).is_none() && self.piece_at(&pawn_square) == Some(&pawn) {
+            Some(square)
+        } else {
+            None
+        }
+    }
+
+    fn fen_for_rank(&self, rank: &Rank) -> String {
+        let mut fen_for_rank = String::new();
+        let mut curr_empty_count: usize = 0;
+
+        for file in File::ALL {
+            if let Some(p) = self.piece_at(&Square::new(file, *rank)) {
+                fen_for_rank += &fen_str(p, curr_empty_count);
+                curr_empty_count = 0;
+            } else {
+                curr_empty_count += 1;
+            }
+        }
+
+        if curr_empty_count != 0 {
+            let _ = write!(fen_for_rank, "{}", curr_empty_count);
+        }
+
+        fen_for_rank
+    }
+}
+
+impl fmt::Display for Position {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        fmt.write_str(&self.to_fen())
+    }
+}
+
+impl FromStr for Position {
+    type Err = FenError;
+
+    fn from_str(fen: &str) -> Result<Self, Self::Err> {
+        Position::from_fen(fen)
+    }
+}
+
+fn fen_str(p: &Side, curr_empty_count: usize) -> String {
+    let mut res = String::new();
+    if curr_empty_count != 0 {
+        let _ = write!(res, "{}", curr_empty_count);
+    }
+    res += p.fen_str();
+    res
+}
+
+/// the en passant target and the side to move after `side` goes from `from` to `to`,
+/// if that's a pawn's double push
+pub fn en_passant_target(side: &Side, from: &Square, to: &Square) -> Option<(Square, Turn)> {
+    if from.file != to.file {
+        return None;
+    }
+
+    match (side, &from.rank, &to.rank) {
+        (Side::White(Kind::Pawn), Rank::Two, Rank::Four) => {
+            Some((Square::new(from.file, Rank::Three), Turn::Black))
+        }
+        (Side::Black(Kind::Pawn), Rank::Seven, Rank::Five) => {
+            Some((Square::new(from.file, Rank::Six), Turn::White))
+        }
+        _ => None,
+    }
+}
+
+/// why a FEN record was rejected, `index` is the character offset of the problem
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FenError {
+    Empty,
+    InvalidPiece {
+        index: usize,
+        found: char,
+    },
+    SplitEmptyCount {
+        index: usize,
+    },
+    RankOverflow {
+        index: usize,
+        rank: Rank,
+    },
+    RankUnderflow {
+        index: usize,
+        rank: Rank,
+        files: u32,
+    },
+    WrongRankCount {
+        index: usize,
+        found: usize,
+    },
+    InvalidSideToMove {
+        index: usize,
+    },
+    InvalidCastling {
+        index: usize,
+    },
+    InvalidEnPassant {
+        index: usize,
+    },
+    InvalidHalfmoveClock {
+        index: usize,
+    },
+    InvalidFullmoveNumber {
+        index: usize,
+    },
+    TooManyFields {
+        index: usize,
+    },
+}
+
+impl FenError {
+    pub fn index(&self) -> usize {
+        match self {
+            Self::Empty => 0,
+            Self::InvalidPiece { index, .. }
+            | Self::SplitEmptyCount { index }
+            | Self::RankOverflow { index, .. }
+            | Self::RankUnderflow { index, .. }
+            | Self::WrongRankCount { index, .. }
+            | Self::InvalidSideToMove { index }
+            | Self::InvalidCastling { index }
+            | Self::InvalidEnPassant { index }
+            | Self::InvalidHalfmoveClock { index }
+            | Self::InvalidFullmoveNumber { index }
+            | Self::TooManyFields { index } => *index,
+        }
+    }
+}
+
+impl fmt::Display for FenError {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Empty => fmt.write_str("there's no FEN to read"),
+            Self::InvalidPiece { index, found } => {
+                write!(
+                    fmt,
+                    "'{}' at {} isn't a piece or an empty count",
+                    found, index
+                )
+            }
+            Self::SplitEmptyCount { index } => write!(
+                fmt,
+                "empty squares at {} should be a single count, not split across digits",
+                index
+            ),
+            Self::RankOverflow { index, rank } => {
+                write!(fmt, "rank {} runs past the h file at {}", rank, index)
+            }
+            Self::RankUnderflow { index, rank, files } => write!(
+                fmt,
+                "rank {} only covers {} of 8 files at {}",
+                rank, files, index
+            ),
+            Self::WrongRankCount { index, found } => {
+                write!(fmt, "expected 8 ranks but found {} at {}", found, index)
+            }
+            Self::InvalidSideToMove { index } => {
+                write!(fmt, "side to move at {} should be 'w' or 'b'", index)
+            }
+            Self::InvalidCastling { index } => write!(
+                fmt,
+                "castling at {} should be '-' or each of 'KQkq' at most once",
+                index
+            ),
+            Self::InvalidEnPassant { index } => write!(
+                fmt,
+                "en passant at {} should be '-' or a square on rank 3 or 6",
+                index
+            ),
+            Self::InvalidHalfmoveClock { index } => {
+                write!(fmt, "halfmove clock at {} should be a number", index)
+            }
+            Self::InvalidFullmoveNumber { index } => write!(
+                fmt,
+                "fullmove number at {} should be a number from 1",
+                index
+            ),
+            Self::TooManyFields { index } => {
+                write!(fmt, "unexpected text after the move counters at {}", index)
+            }
+        }
+    }
+}
+
+impl std::error::Error for FenError {}
+
+// whitespace separated fields along with the character offset they start at
+fn fields_with_index(fen: &str) -> Vec<(usize, &str)> {
+    let mut fields = vec![];
+    let mut start: Option<(usize, usize)> = None;
+
+    for (index, (byte, c)) in fen.char_indices().enumerate() {
+        match (c.is_whitespace(), start) {
+            (true, Some((start_index, start_byte))) => {
+                fields.push((start_index, &fen[start_byte..byte]));
+                start = None;
+            }
+            (false, None) => start = Some((index, byte)),
+            _ => {}
+        }
+    }
+
+    if let Some((start_index, start_byte)) = start {
+        fields.push((start_index, &fen[start_byte..]));
+    }
+
+    fields
+}
+
+fn parse_placement(index: usize, placement: &str) -> Result<Vec<(Square, Side)>, FenError> {
+    // placement runs from the eighth rank down
+    let rank = |rank_num: usize| Rank::ALL[7 - rank_num];
+    let rank_count = placement.split('/').count();
+
+    let mut pieces = vec![];
+    let mut rank_num = 0;
+    let mut file_num = 0;
+    let mut after_digit = false;
+
+    for (offset, c) in placement.chars().enumerate() {
+        let index = index + offset;
+
+        if c == '/' {
+            if file_num < 8 {
+                return Err(FenError::RankUnderflow {
+                    index,
+                    rank: rank(rank_num),
+                    files: file_num,
+                });
+            }
+            if rank_num == 7 {
+                return Err(FenError::WrongRankCount {
+                    index,
+                    found: rank_count,
+                });
+            }
+            rank_num += 1;
+            file_num = 0;
+            after_digit = false;
+            continue;
+        }
+
+        if file_num >= 8 {
+            return Err(FenError::RankOverflow {
+                index,
+                rank: rank(rank_num),
+            });
+        }
+
+        match (c.to_digit(10), Side::from_fen_char(c)) {
+            (Some(d), _) if (1..=8).contains(&d) => {
+                if after_digit {
+                    return Err(FenError::SplitEmptyCount { index });
+                }
+                after_digit = true;
+                file_num += d;
+                if file_num > 8 {
+                    return Err(FenError::RankOverflow {
+                        index,
+                        rank: rank(rank_num),
+                    });
+                }
+            }
+            (_, Some(side)) => {
+                after_digit = false;
+                pieces.push((
+                    Square::new(File::ALL[file_num as usize], rank(rank_num)),
+                    side,
+                ));
+                file_num += 1;
+            }
+            _ => return Err(FenError::InvalidPiece { index, found: c }),
+        }
+    }
+
+    let index = index + placement.chars().count();
+
+    if file_num < 8 {
+        return Err(FenError::RankUnderflow {
+            index,
+            rank: rank(rank_num),
+            files: file_num,
+        });
+    }
+
+    if rank_num != 7 {
+        return Err(FenError::WrongRankCount {
+            index,
+            found: rank_count,
+        });
+    }
+
+    Ok(pieces)
+}
+
+// the castling rights that are listed, each of them at most once
+fn parse_castling(index: usize, castling: &str) -> Result<CastlingRights, FenError> {
+    let mut rights = CastlingRights::default();
+
+    if castling == "-" {
+        return Ok(rights);
+    }
+
+    for (offset, c) in castling.chars().enumerate() {
+        let right = match c {
+            'K' => &mut rights.white_kingside,
+            'Q' => &mut rights.white_queenside,
+            'k' => &mut rights.black_kingside,
+            'q' => &mut rights.black_queenside,
+            _ => {
+                return Err(FenError::InvalidCastling {
+                    index: index + offset,
+                })
+            }
+        };
+
+        if *right {
+            // listed twice
+            return Err(FenError::InvalidCastling {
+                index: index + offset,
+            });
+        }
+        *right = true;
+    }
+
+    Ok(rights)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn error(fen: &str) -> FenError {
+        Position::from_fen(fen).unwrap_err()
+    }
+
+    fn position(fen: &str) -> Position {
+        Position::from_fen(fen).unwrap()
+    }
+
+    #[test]
+    fn fen_round_trips() {
+        for fen in [
+            STARTING_FEN,
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            "r3k2r/8/8/8/8/8/8/R3K2R b Kq - 12 40",
+            // en passant targets for either side, with the pushed pawn still there
+            "rnbqkbnr/pppp1ppp/8/8/3pP3/8/PPP2PPP/RNBQKBNR b KQkq e3 0 3",
+            "rnbqkbnr/ppp2ppp/8/3pP3/8/8/PPPP1PPP/RNBQKBNR w KQkq d6 0 3",
+        ] {
+            assert_eq!(position(fen).to_fen(), fen);
+            assert_eq!(fen.parse::<Position>().unwrap().to_string(), fen);
+        }
+    }
+
+    #[test]
+    fn missing_fields_default() {
+        assert_eq!(
+            position("8/8/8/8/8/8/8/8").to_fen(),
+            "8/8/8/8/8/8/8/8 w - - 0 1"
+        );
+        assert_eq!(position("8/8/8/8/8/8/8/8 b").turn, Turn::Black);
+    }
+
+    #[test]
+    fn starting_position() {
+        let starting = Position::starting();
+        assert_eq!(starting.pieces().count(), 32);
+        assert_eq!(
+            starting.piece_at(&"e1".parse().unwrap()),
+            Some(&Side::White(Kind::King))
+        );
+        assert_eq!(starting.piece_at(&"e4".parse().unwrap()), None);
+        assert_eq!(starting.castling_available(), CastlingRights::all());
+    }
+
+    #[test]
+    fn castling_needs_king_and_rook_home() {
+        // the white king's moved off e1, and black's kingside rook is gone
+        let moved = position("r3k3/8/8/8/8/8/8/R4K1R w KQkq - 0 1");
+        assert_eq!(moved.castling, CastlingRights::all());
+        assert_eq!(
+            moved.castling_available(),
+            CastlingRights {
+                black_queenside: true,
+                ..CastlingRights::default()
+            }
+        );
+        assert_eq!(moved.to_fen(), "r3k3/8/8/8/8/8/8/R4K1R w q - 0 1");
+
+        // rights that aren't claimed stay off even with everything home
+        let unclaimed = position("r3k2r/8/8/8/8/8/8/R3K2R w Kq - 0 1");
+        assert_eq!(unclaimed.castling_available().fen_str(), "Kq");
+    }
+
+    #[test]
+    fn en_passant_needs_the_pushed_pawn() {
+        let valid = |fen: &str| {
+            position(fen)
+                .valid_en_passant()
+                .map(|square| square.to_string())
+        };
+
+        assert_eq!(
+            valid("4k3/8/8/8/4P3/8/8/4K3 b - e3 0 1"),
+            Some("e3".to_string())
+        );
+        assert_eq!(
+            valid("4k3/8/8/3p4/8/8/8/4K3 w - d6 0 1"),
+            Some("d6".to_string())
+        );
+        // white's target with white to move
+        assert_eq!(valid("4k3/8/8/8/4P3/8/8/4K3 w - e3 0 1"), None);
+        // no pawn in front of it
+        assert_eq!(valid("4k3/8/8/8/8/8/8/4K3 b - e3 0 1"), None);
+        // something's on the target square
+        assert_eq!(valid("4k3/8/8/8/4P3/4N3/8/4K3 b - e3 0 1"), None);
+        assert_eq!(
+            position("4k3/8/8/8/8/8/8/4K3 b - e3 0 1").to_fen(),
+            "4k3/8/8/8/8/8/8/4K3 b - - 0 1"
+        );
+    }
+
+    #[test]
+    fn empty_fen() {
+        assert_eq!(error(""), FenError::Empty);
+        assert_eq!(error("   "), FenError::Empty);
+        assert_eq!(error("").index(), 0);
+    }
+
+    #[test]
+    fn invalid_piece() {
+        assert_eq!(
+            error("rnbqkbnr/ppppXppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1"),
+            FenError::InvalidPiece {
+                index: 13,
+                found: 'X'
+            }
+        );
+        // there's no nine square rank to count
+        assert_eq!(
+            error("9/8/8/8/8/8/8/8"),
+            FenError::InvalidPiece {
+                index: 0,
+                found: '9'
+            }
+        );
+    }
+
+    #[test]
+    fn split_empty_count() {
+        assert_eq!(
+            error("rnbqkbnr/pppppppp/44/8/8/8/PPPPPPPP/RNBQKBNR"),
+            FenError::SplitEmptyCount { index: 19 }
+        );
+    }
+
+    #[test]
+    fn rank_overflow() {
+        assert_eq!(
+            error("rnbqkbnr/ppppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR"),
+            FenError::RankOverflow {
+                index: 17,
+                rank: Rank::Seven
+            }
+        );
+        assert_eq!(
+            error("8/8/8/8/8/8/7P2/8"),
+            FenError::RankOverflow {
+                index: 14,
+                rank: Rank::Two
+            }
+        );
+    }
+
+    #[test]
+    fn rank_underflow() {
+        assert_eq!(
+            error("rnbqkbnr/ppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR"),
+            FenError::RankUnderflow {
+                index: 16,
+                rank: Rank::Seven,
+                files: 7
+            }
+        );
+        // the last rank runs out at the end of the field
+        assert_eq!(
+            error("8/8/8/8/8/8/8/7 w"),
+            FenError::RankUnderflow {
+                index: 15,
+                rank: Rank::One,
+                files: 7
+            }
+        );
+    }
+
+    #[test]
+    fn wrong_rank_count() {
+        assert_eq!(
+            error("rnbqkbnr/pppppppp/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1"),
+            FenError::WrongRankCount {
+                index: 41,
+                found: 7
+            }
+        );
+        assert_eq!(
+            error("8/8/8/8/8/8/8/8/8"),
+            FenError::WrongRankCount {
+                index: 15,
+                found: 9
+            }
+        );
+    }
+
+    #[test]
+    fn invalid_side_to_move() {
+        assert_eq!(
+            error("8/8/8/8/8/8/8/8 x"),
+            FenError::InvalidSideToMove { index: 16 }
+        );
+        // indices count characters, not bytes
+        assert_eq!(
+            error("\u{3000}8/8/8/8/8/8/8/8 é"),
+            FenError::InvalidSideToMove { index: 17 }
+        );
+    }
+
+    #[test]
+    fn invalid_castling() {
+        assert_eq!(
+            error("8/8/8/8/8/8/8/8 w KX"),
+            FenError::InvalidCastling { index: 19 }
+        );
+        // listed twice
+        assert_eq!(
+            error("8/8/8/8/8/8/8/8 w KQkK"),
+            FenError::InvalidCastling { index: 21 }
+        );
+    }
+
+    #[test]
+    fn invalid_en_passant() {
+        assert_eq!(
+            error("8/8/8/8/8/8/8/8 w - e4"),
+            FenError::InvalidEnPassant { index: 20 }
+        );
+        assert_eq!(
+            error("8/8/8/8/8/8/8/8 w - i6"),
+            FenError::InvalidEnPassant { index: 20 }
+        );
+    }
+
+    #[test]
+    fn invalid_counters() {
+        assert_eq!(
+            error("8/8/8/8/8/8/8/8 w - - x 1"),
+            FenError::InvalidHalfmoveClock { index: 22 }
+        );
+        assert_eq!(
+            error("8/8/8/8/8/8/8/8 w - - 0 0"),
+            FenError::InvalidFullmoveNumber { index: 24 }
+        );
+        assert_eq!(
+            error("8/8/8/8/8/8/8/8 w - - 0 -1"),
+            FenError::InvalidFullmoveNumber { index: 24 }
+        );
+    }
+
+    #[test]
+    fn too_many_fields() {
+        assert_eq!(
+            error("8/8/8/8/8/8/8/8 w - - 0 1 extra"),
+            FenError::TooManyFields { index: 26 }
+        );
+    }
+}
@@ -0,0 +1,326 @@
+use crate::{
+    moves::Move,
+    position::{Kind, Position},
+    square::{File, Rank, Square},
+};
+
+/// the letter a piece goes by in a move, pawns don't get one
+pub fn piece_letter(kind: &Kind) -> Option<char> {
+    match kind {
+        Kind::Pawn => None,
+        Kind::Rook => Some('R'),
+        Kind::Knight => Some('N'),
+        Kind::Bishop => Some('B'),
+        Kind::Queen => Some('Q'),
+        Kind::King => Some('K'),
+    }
+}
+
+impl Position {
+    /// a legal move in standard algebraic notation, like "Nbd7", "exd6", "O-O-O" or "e8=Q#"
+    pub fn san(&self, mv: &Move) -> String {
+        let side = match self.piece_at(&mv.from) {
+            Some(side) => side,
+            None => return mv.to_string(),
+        };
+        let kind = side.kind();
+        let mut san = String::new();
+
+        if *kind == Kind::King && mv.from.file_distance(&mv.to) == 2 {
+            san.push_str(if mv.to.file == File::G {
+                "O-O"
+            } else {
+                "O-O-O"
+            });
+        } else {
+            let capture = self.piece_at(&mv.to).is_some()
+                || (*kind == Kind::Pawn && mv.from.file != mv.to.file);
+
+            match piece_letter(kind) {
+                Some(letter) => {
+                    san.push(letter);
+                    san.push_str(&self.disambiguation(mv));
+                }
+                // a pawn taking is known by the file it came from
+                None if capture => san.push(mv.from.file.to_char()),
+                None => {}
+            }
+            if capture {
+                san.push('x');
+            }
+            san.push_str(&mv.to.to_string());
+            if let Some(letter) = mv.promotion.as_ref().and_then(piece_letter) {
+                san.push('=');
+                san.push(letter);
+            }
+        }
+
+        let mut after = self.clone();
+        after.play(mv);
+        if after.is_checkmate() {
+            san.push('#');
+        } else if after.in_check() {
+            san.push('+');
+        }
+
+        san
+    }
+
+    // as little of the from square as tells the move apart from the same kind of
+    // piece going to the same square: the file, then the rank, then both
+    fn disambiguation(&self, mv: &Move) -> String {
+        let side = self.piece_at(&mv.from);
+        let others: Vec<_> = self
+            .legal_moves()
+            .into_iter()
+            .filter(|other| {
+                other.to == mv.to && other.from != mv.from && self.piece_at(&other.from) == side
+            })
+            .map(|other| other.from)
+            .collect();
+
+        if others.is_empty() {
+            String::new()
+        } else if others.iter().all(|other| other.file != mv.from.file) {
+            mv.from.file.to_string()
+        } else if others.iter().all(|other| other.rank != mv.from.rank) {
+            mv.from.rank.to_string()
+        } else {
+            mv.from.to_string()
+        }
+    }
+
+    /// the legal move `san` describes, if there's exactly one. check marks and
+    /// annotations are ignored, and so is a from square given when it isn't needed
+    pub fn parse_san(&self, san: &str) -> Option<Move> {
+        let san = san.trim_end_matches(['+', '#', '!', '?']);
+        let legal = self.legal_moves();
+        let kind_from = |mv: &Move| self.piece_at(&mv.from).map(|side| side.kind().clone());
+
+        if let Some(queenside) = match san {
+            "O-O" | "0-0" => Some(false),
+            "O-O-O" | "0-0-0" => Some(true),
+            _ => None,
+        } {
+            let file = if queenside { File::C } else { File::G };
+            return legal.into_iter().find(|mv| {
+                kind_from(mv) == Some(Kind::King)
+                    && mv.from.file_distance(&mv.to) == 2
+                    && mv.to.file == file
+            });
+        }
+
+        let mut chars: Vec<char> = san.chars().filter(|c| *c != 'x' && *c != '-').collect();
+
+        let kind = match chars.first().and_then(|c| letter_kind(*c)) {
+            Some(kind) => {
+                chars.remove(0);
+                kind
+            }
+            None => Kind::Pawn,
+        };
+
+        // "e8=Q" or "e8Q"
+        let promotion = match chars.last().and_then(|c| letter_kind(*c)) {
+            Some(promotion) => {
+                chars.pop();
+                if chars.last() == Some(&'=') {
+                    chars.pop();
+                }
+                Some(promotion)
+            }
+            None => None,
+        };
+
+        if chars.len() < 2 {
+            return None;
+        }
+        let to: String = chars.split_off(chars.len() - 2).into_iter().collect();
+        let to: Square = to.parse().ok()?;
+
+        // whatever's left tells apart pieces that could both get there
+        let mut from_file = None;
+        let mut from_rank = None;
+        for c in chars {
+            match (File::from_char(c), Rank::from_char(c)) {
+                (Some(file), _) => from_file = Some(file),
+                (_, Some(rank)) => from_rank = Some(rank),
+                _ => return None,
+            }
+        }
+
+        let mut matching = legal.into_iter().filter(|mv| {
+            mv.to == to
+                && kind_from(mv) == Some(kind.clone())
+                && mv.promotion == promotion
+                && from_file.as_ref().is_none_or(|file| mv.from.file == *file)
+                && from_rank.as_ref().is_none_or(|rank| mv.from.rank == *rank)
+        });
+
+        match (matching.next(), matching.next()) {
+            (Some(mv), None) => Some(mv),
+            _ => None,
+        }
+    }
+
+    /// `moves` played one after another from here, in standard algebraic notation. stops
+    /// at the first one that isn't legal
+    pub fn san_line(&self, moves: &[Move]) -> Vec<String> {
+        let mut position = self.clone();
+        let mut line = vec![];
+        for mv in moves {
+            if !position.is_legal(mv) {
+                break;
+            }
+            line.push(position.san(mv));
+            position.play(mv);
+        }
+        line
+    }
+}
+
+fn letter_kind(letter: char) -> Option<Kind> {
+    match letter {
+        'R' => Some(Kind::Rook),
+        'N' => Some(Kind::Knight),
+        'B' => Some(Kind::Bishop),
+        'Q' => Some(Kind::Queen),
+        'K' => Some(Kind::King),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::position::STARTING_FEN;
+
+    fn position(fen: &str) -> Position {
+        Position::from_fen(fen).unwrap()
+    }
+
+    fn san(fen: &str, mv: &str) -> String {
+        position(fen).san(&mv.parse().unwrap())
+    }
+
+    fn parse(fen: &str, san: &str) -> Option<String> {
+        position(fen).parse_san(san).map(|mv| mv.to_string())
+    }
+
+    #[test]
+    fn disambiguation() {
+        // by file, knights on b8 and f6
+        let knights = "rn2k3/8/5n2/8/8/8/8/4K3 b - - 0 1";
+        assert_eq!(san(knights, "b8d7"), "Nbd7");
+        assert_eq!(san(knights, "f6d7"), "Nfd7");
+        // by rank, rooks on e1 and e3
+        let rooks = "k7/8/8/8/8/4R3/8/4R2K w - - 0 1";
+        assert_eq!(san(rooks, "e1e2"), "R1e2");
+        assert_eq!(san(rooks, "e3e2"), "R3e2");
+        // by both, queens on h4, e4 and h1
+        let queens = "2k5/8/8/8/4Q2Q/8/8/K6Q w - - 0 1";
+        assert_eq!(san(queens, "h4e1"), "Qh4e1");
+        assert_eq!(san(queens, "e4e1"), "Qee1");
+        assert_eq!(san(queens, "h1e1"), "Q1e1");
+        // nothing else can get there
+        assert_eq!(san(STARTING_FEN, "g1f3"), "Nf3");
+    }
+
+    #[test]
+    fn promotion() {
+        assert_eq!(san("4k3/P7/8/8/8/8/8/4K3 w - - 0 1", "a7a8q"), "a8=Q+");
+        let capture = "1r2k3/P7/8/8/8/8/8/4K3 w - - 0 1";
+        assert_eq!(san(capture, "a7b8q"), "axb8=Q+");
+        assert_eq!(san(capture, "a7b8n"), "axb8=N");
+        assert_eq!(parse(capture, "axb8=N"), Some("a7b8n".to_string()));
+        assert_eq!(parse(capture, "axb8Q"), Some("a7b8q".to_string()));
+        // a promotion has to say what to
+        assert_eq!(parse(capture, "axb8"), None);
+    }
+
+    #[test]
+    fn castling() {
+        let fen = "r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1";
+        assert_eq!(san(fen, "e1g1"), "O-O");
+        assert_eq!(san(fen, "e1c1"), "O-O-O");
+        assert_eq!(parse(fen, "O-O"), Some("e1g1".to_string()));
+        assert_eq!(parse(fen, "0-0-0"), Some("e1c1".to_string()));
+        assert_eq!(parse("r3k2r/8/8/8/8/8/8/R3K2R w - - 0 1", "O-O"), None);
+    }
+
+    #[test]
+    fn check_and_mate() {
+        assert_eq!(san("4k3/8/8/8/8/8/8/R3K3 w - - 0 1", "a1a8"), "Ra8+");
+        let fools_mate = "rnbqkbnr/pppp1ppp/8/4p3/6P1/5P2/PPPPP2P/RNBQKBNR b KQkq - 0 2";
+        assert_eq!(san(fools_mate, "d8h4"), "Qh4#");
+        // the marks aren't needed to read a move
+        assert_eq!(parse(fools_mate, "Qh4#"), Some("d8h4".to_string()));
+        assert_eq!(parse(fools_mate, "Qh4"), Some("d8h4".to_string()));
+        assert_eq!(parse(fools_mate, "Qh4!?"), Some("d8h4".to_string()));
+    }
+
+    #[test]
+    fn en_passant() {
+        let fen = "4k3/8/8/3pP3/8/8/8/4K3 w - d6 0 1";
+        assert_eq!(san(fen, "e5d6"), "exd6");
+        assert_eq!(parse(fen, "exd6"), Some("e5d6".to_string()));
+    }
+
+    #[test]
+    fn parse_needs_exactly_one_move() {
+        let knights = "rn2k3/8/5n2/8/8/8/8/4K3 b - - 0 1";
+        assert_eq!(parse(knights, "Nd7"), None);
+        assert_eq!(parse(knights, "Nbd7"), Some("b8d7".to_string()));
+        // more of the from square than it needs is fine
+        assert_eq!(parse(knights, "Nb8d7"), Some("b8d7".to_string()));
+        assert_eq!(parse(knights, "Nd5"), Some("f6d5".to_string()));
+        assert_eq!(parse(knights, "Ne4e5"), None);
+        assert_eq!(parse(STARTING_FEN, "e5"), None);
+        assert_eq!(parse(STARTING_FEN, "Zf3"), None);
+        assert_eq!(parse(STARTING_FEN, ""), None);
+    }
+
+    // every move from every position `depth` moves deep reads back as itself
+    fn assert_round_trip(position: &Position, depth: u32) {
+        if depth == 0 {
+            return;
+        }
+        let legal = position.legal_moves();
+        for mv in &legal {
+            let san = position.san(mv);
+            assert_eq!(
+                position.parse_san(&san).as_ref(),
+                Some(mv),
+                "{} in {}",
+                san,
+                position
+            );
+
+            let mut next = position.clone();
+            next.play(mv);
+            assert_round_trip(&next, depth - 1);
+        }
+    }
+
+    #[test]
+    fn round_trip() {
+        for (fen, depth) in [
+            (STARTING_FEN, 3),
+            (
+                "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+                2,
+            ),
+            ("8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1", 3),
+            (
+                "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1",
+                2,
+            ),
+            (
+                "rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8",
+                2,
+            ),
+        ] {
+            assert_round_trip(&position(fen), depth);
+        }
+    }
+}
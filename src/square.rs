@@ -0,0 +1,320 @@
+use std::{fmt, str::FromStr};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum File {
+    A,
+    B,
+    C,
+    D,
+    E,
+    F,
+    G,
+    H,
+}
+
+impl File {
+    pub const ALL: [File; 8] = [
+        File::A,
+        File::B,
+        File::C,
+        File::D,
+        File::E,
+        File::F,
+        File::G,
+        File::H,
+    ];
+
+    /// 0 for the a file through 7 for the h file
+    pub fn index(&self) -> usize {
+        *self as usize
+    }
+
+    pub fn from_index(index: usize) -> Option<File> {
+        File::ALL.get(index).copied()
+    }
+
+    pub fn to_char(&self) -> char {
+        (b'a' + self.index() as u8) as char
+    }
+
+    pub fn from_char(c: char) -> Option<File> {
+        File::ALL.iter().find(|file| file.to_char() == c).copied()
+    }
+
+    /// the file `by` files to the right, towards h, if that's still on the board
+    pub fn offset(&self, by: i8) -> Option<File> {
+        let index = (self.index() as i8).checked_add(by)?;
+        if index < 0 {
+            return None;
+        }
+        File::from_index(index as usize)
+    }
+}
+
+impl fmt::Display for File {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        write!(fmt, "{}", self.to_char())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Rank {
+    One,
+    Two,
+    Three,
+    Four,
+    Five,
+    Six,
+    Seven,
+    Eight,
+}
+
+impl Rank {
+    pub const ALL: [Rank; 8] = [
+        Rank::One,
+        Rank::Two,
+        Rank::Three,
+        Rank::Four,
+        Rank::Five,
+        Rank::Six,
+        Rank::Seven,
+        Rank::Eight,
+    ];
+
+    /// 0 for the first rank through 7 for the eighth
+    pub fn index(&self) -> usize {
+        *self as usize
+    }
+
+    pub fn from_index(index: usize) -> Option<Rank> {
+        Rank::ALL.get(index).copied()
+    }
+
+    pub fn to_char(&self) -> char {
+        (b'1' + self.index() as u8) as char
+    }
+
+    pub fn from_char(c: char) -> Option<Rank> {
+        Rank::ALL.iter().find(|rank| rank.to_char() == c).copied()
+    }
+
+    /// the rank `by` ranks up, towards the eighth, if that's still on the board
+    pub fn offset(&self, by: i8) -> Option<Rank> {
+        let index = (self.index() as i8).checked_add(by)?;
+        if index < 0 {
+            return None;
+        }
+        Rank::from_index(index as usize)
+    }
+}
+
+impl fmt::Display for Rank {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        write!(fmt, "{}", self.to_char())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Square {
+    pub file: File,
+    pub rank: Rank,
+}
+
+impl fmt::Display for Square {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        write!(fmt, "{}{}", self.file, self.rank)
+    }
+}
+
+/// one step in every direction as (files, ranks), clockwise from up the board
+pub const STEPS: [(i8, i8); 8] = [
+    (0, 1),
+    (1, 1),
+    (1, 0),
+    (1, -1),
+    (0, -1),
+    (-1, -1),
+    (-1, 0),
+    (-1, 1),
+];
+
+impl Square {
+    pub fn new(file: File, rank: Rank) -> Square {
+        Square { file, rank }
+    }
+
+    /// 0 for a1, 1 for b1, through 63 for h8
+    pub fn index(&self) -> usize {
+        self.rank.index() * 8 + self.file.index()
+    }
+
+    pub fn from_index(index: usize) -> Option<Square> {
+        if index >= 64 {
+            return None;
+        }
+        Some(Square::new(
+            File::from_index(index % 8)?,
+            Rank::from_index(index / 8)?,
+        ))
+    }
+
+    /// all 64 squares, a1 through h1 then on up the board to h8
+    pub fn all() -> impl Iterator<Item = Square> {
+        (0..64).filter_map(Square::from_index)
+    }
+
+    /// the square `files` to the right and `ranks` up, if that's still on the board
+    pub fn offset(&self, files: i8, ranks: i8) -> Option<Square> {
+        Some(Square::new(
+            self.file.offset(files)?,
+            self.rank.offset(ranks)?,
+        ))
+    }
+
+    /// the squares touching this one, three to eight of them depending on the edges
+    pub fn neighbours(&self) -> impl Iterator<Item = Square> + '_ {
+        STEPS
+            .iter()
+            .filter_map(move |(files, ranks)| self.offset(*files, *ranks))
+    }
+
+    /// a1 is dark, h1 is light
+    pub fn is_light(&self) -> bool {
+        (self.file.index() + self.rank.index()) % 2 == 1
+    }
+
+    /// how many files apart the two squares are
+    pub fn file_distance(&self, other: &Square) -> usize {
+        self.file.index().abs_diff(other.file.index())
+    }
+
+    /// how many ranks apart the two squares are
+    pub fn rank_distance(&self, other: &Square) -> usize {
+        self.rank.index().abs_diff(other.rank.index())
+    }
+
+    /// the number of king moves between the two squares
+    pub fn distance(&self, other: &Square) -> usize {
+        self.file_distance(other).max(self.rank_distance(other))
+    }
+}
+
+/// text that isn't a file letter followed by a rank number, like "e4"
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseSquareError;
+
+impl fmt::Display for ParseSquareError {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        fmt.write_str("a square is a file from a to h followed by a rank from 1 to 8")
+    }
+}
+
+impl std::error::Error for ParseSquareError {}
+
+impl FromStr for Square {
+    type Err = ParseSquareError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut chars = s.chars();
+        let file = chars.next().and_then(File::from_char);
+        let rank = chars.next().and_then(Rank::from_char);
+        match (file, rank, chars.next()) {
+            (Some(file), Some(rank), None) => Ok(Square::new(file, rank)),
+            _ => Err(ParseSquareError),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn square(s: &str) -> Square {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn files_and_ranks() {
+        for (index, file) in File::ALL.iter().enumerate() {
+            assert_eq!(file.index(), index);
+            assert_eq!(File::from_index(index), Some(*file));
+            assert_eq!(File::from_char(file.to_char()), Some(*file));
+        }
+        for (index, rank) in Rank::ALL.iter().enumerate() {
+            assert_eq!(rank.index(), index);
+            assert_eq!(Rank::from_index(index), Some(*rank));
+            assert_eq!(Rank::from_char(rank.to_char()), Some(*rank));
+        }
+        assert_eq!(File::H.to_char(), 'h');
+        assert_eq!(Rank::Eight.to_char(), '8');
+        assert_eq!(File::from_index(8), None);
+        assert_eq!(Rank::from_index(8), None);
+        assert_eq!(File::from_char('i'), None);
+        assert_eq!(File::from_char('A'), None);
+        assert_eq!(Rank::from_char('0'), None);
+        assert_eq!(Rank::from_char('9'), None);
+    }
+
+    #[test]
+    fn offset() {
+        assert_eq!(File::A.offset(7), Some(File::H));
+        assert_eq!(File::H.offset(-7), Some(File::A));
+        assert_eq!(File::A.offset(-1), None);
+        assert_eq!(File::H.offset(1), None);
+        assert_eq!(Rank::One.offset(-1), None);
+        assert_eq!(Rank::Eight.offset(1), None);
+        // far off the board either way, without overflowing
+        assert_eq!(File::H.offset(i8::MAX), None);
+        assert_eq!(File::A.offset(i8::MIN), None);
+        assert_eq!(Rank::Eight.offset(i8::MAX), None);
+        assert_eq!(Rank::One.offset(i8::MIN), None);
+
+        assert_eq!(square("e4").offset(1, 2), Some(square("f6")));
+        assert_eq!(square("h8").offset(-7, -7), Some(square("a1")));
+        assert_eq!(square("a1").offset(-1, 0), None);
+        assert_eq!(square("h8").offset(0, 1), None);
+        assert_eq!(square("a1").neighbours().count(), 3);
+        assert_eq!(square("a4").neighbours().count(), 5);
+        assert_eq!(square("e4").neighbours().count(), 8);
+    }
+
+    #[test]
+    fn squares() {
+        for (index, sq) in Square::all().enumerate() {
+            assert_eq!(sq.index(), index);
+            assert_eq!(Square::from_index(index), Some(sq));
+            assert_eq!(square(&sq.to_string()), sq);
+        }
+        let all: Vec<String> = Square::all().map(|sq| sq.to_string()).collect();
+        assert_eq!(all.len(), 64);
+        assert_eq!(all[..3], ["a1", "b1", "c1"]);
+        assert_eq!(all[8], "a2");
+        assert_eq!(all[63], "h8");
+        assert_eq!(Square::from_index(64), None);
+    }
+
+    #[test]
+    fn parse() {
+        assert_eq!(square("e4"), Square::new(File::E, Rank::Four));
+        for bad in ["", "e", "4e", "e9", "i4", "E4", "e44", " e4"] {
+            assert_eq!(bad.parse::<Square>(), Err(ParseSquareError), "{:?}", bad);
+        }
+    }
+
+    #[test]
+    fn colours_and_distance() {
+        assert!(!square("a1").is_light());
+        assert!(square("h1").is_light());
+        assert!(square("a8").is_light());
+        assert!(!square("h8").is_light());
+        assert!(square("d1").is_light());
+        assert!(!square("e1").is_light());
+
+        assert_eq!(square("a1").distance(&square("h8")), 7);
+        assert_eq!(square("e4").distance(&square("e4")), 0);
+        assert_eq!(square("b1").distance(&square("c3")), 2);
+        assert_eq!(square("c3").file_distance(&square("a7")), 2);
+        assert_eq!(square("c3").rank_distance(&square("a7")), 4);
+        assert_eq!(square("c3").distance(&square("a7")), 4);
+    }
+}
@@ -1,17 +1,6 @@
-use std::collections::HashMap;
+use bevy::prelude::{With, Without};
 
-use bevy::{
-    math::Vec2,
-    prelude::{With, Without},
-};
-
-use crate::{
-    control_ux::ControlsText,
-    fen::FenText,
-    pieces::{ActivePiece, SelectedPiece, SourcePiece},
-};
-
-pub type Board = HashMap<&'static str, Vec2>;
+use crate::pieces::{ActivePiece, SelectedPiece, SourcePiece};
 
 pub type WithSelectedPiece = (
     With<SelectedPiece>,
@@ -19,8 +8,6 @@ pub type WithSelectedPiece = (
     Without<ActivePiece>,
 );
 
-pub type WithFenText = (With<FenText>, Without<ControlsText>);
-
 pub type WithActivePiece = (
     With<ActivePiece>,
     Without<SourcePiece>,
@@ -0,0 +1,609 @@
+use crate::{moves::Move, position::Position};
+use std::{
+    io::{self, BufRead, BufReader, Write},
+    path::Path,
+    process::{Child, ChildStdin, Command, Stdio},
+    sync::{
+        mpsc::{self, Receiver, TryRecvError},
+        Mutex,
+    },
+    thread,
+    time::Duration,
+};
+
+// how long an engine gets to quit on its own before it's killed
+const QUIT_WAIT: Duration = Duration::from_millis(200);
+
+/// how the position looks to an engine, for the side to move
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Score {
+    // hundredths of a pawn
+    Centipawns(i32),
+    // moves to mate, negative when it's the side to move getting mated
+    Mate(i32),
+}
+
+/// what an engine's found so far, from an `info` line. anything it didn't say is left out
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Info {
+    pub depth: Option<u32>,
+    pub seldepth: Option<u32>,
+    // which of the best lines this is, from 1
+    pub multipv: Option<u32>,
+    pub score: Option<Score>,
+    pub nodes: Option<u64>,
+    pub nps: Option<u64>,
+    // milliseconds spent searching
+    pub time: Option<u64>,
+    // the moves it expects, starting with the one it'd play
+    pub pv: Vec<Move>,
+    pub string: Option<String>,
+}
+
+impl Info {
+    fn parse(words: &[&str]) -> Info {
+        let mut info = Info::default();
+        let mut words = words.iter().peekable();
+        while let Some(word) = words.next() {
+            match *word {
+                "depth" => info.depth = words.next().and_then(|w| w.parse().ok()),
+                "seldepth" => info.seldepth = words.next().and_then(|w| w.parse().ok()),
+                "multipv" => info.multipv = words.next().and_then(|w| w.parse().ok()),
+                "nodes" => info.nodes = words.next().and_then(|w| w.parse().ok()),
+                "nps" => info.nps = words.next().and_then(|w| w.parse().ok()),
+                "time" => info.time = words.next().and_then(|w| w.parse().ok()),
+                "score" => {
+                    let kind = words.next();
+                    let value = words.next().and_then(|w| w.parse().ok());
+                    info.score = match (kind, value) {
+                        (Some(&"cp"), Some(value)) => Some(Score::Centipawns(value)),
+                        (Some(&"mate"), Some(value)) => Some(Score::Mate(value)),
+                        _ => None,
+                    };
+                }
+                "pv" => {
+                    while let Some(mv) = words.peek().and_then(|w| w.parse().ok()) {
+                        info.pv.push(mv);
+                        words.next();
+                    }
+                }
+                // the rest of the line is for people to read
+                "string" => {
+                    info.string = Some(words.by_ref().copied().collect::<Vec<_>>().join(" "))
+                }
+                // currmove, hashfull, tbhits and the like aren't shown, and their
+                // values are skipped over the same way
+                _ => {}
+            }
+        }
+        info
+    }
+}
+
+/// one of the settings an engine offers, from an `option` line
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EngineOption {
+    pub name: String,
+    pub kind: OptionKind,
+}
+
+/// the kind of value an option takes, and what it starts as
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum OptionKind {
+    Check {
+        default: bool,
+    },
+    Spin {
+        default: i64,
+        min: i64,
+        max: i64,
+    },
+    Combo {
+        default: String,
+        choices: Vec<String>,
+    },
+    // something the engine does when it's told to, like clearing its hash table
+    Button,
+    String {
+        default: String,
+    },
+}
+
+impl OptionKind {
+    /// the default value as it would be sent back with `setoption`
+    pub fn default_value(&self) -> String {
+        match self {
+            Self::Check { default } => default.to_string(),
+            Self::Spin { default, .. } => default.to_string(),
+            Self::Combo { default, .. } | Self::String { default } => default.clone(),
+            Self::Button => String::new(),
+        }
+    }
+}
+
+impl EngineOption {
+    // "name Skill Level type spin default 20 min 0 max 20", with names and values
+    // running over as many words as they need
+    fn parse(words: &[&str]) -> Option<EngineOption> {
+        let mut fields: Vec<(&str, Vec<&str>)> = vec![];
+        for word in words {
+            match (*word, fields.last_mut()) {
+                ("name" | "type" | "default" | "min" | "max" | "var", _) => {
+                    fields.push((*word, vec![]))
+                }
+                (_, Some((_, value))) => value.push(*word),
+                (_, None) => return None,
+            }
+        }
+
+        let field = |key: &str| {
+            fields
+                .iter()
+                .find(|(name, _)| *name == key)
+                .map(|(_, value)| value.join(" "))
+        };
+        // "<empty>" stands in for a string with nothing in it
+        let text = |key: &str| {
+            field(key)
+                .filter(|value| value != "<empty>")
+                .unwrap_or_default()
+        };
+        let number = |key: &str| field(key).and_then(|value| value.parse().ok());
+
+        let kind = match field("type")?.as_str() {
+            "check" => OptionKind::Check {
+                default: field("default")? == "true",
+            },
+            "spin" => OptionKind::Spin {
+                default: number("default")?,
+                min: number("min")?,
+                max: number("max")?,
+            },
+            "combo" => OptionKind::Combo {
+                default: text("default"),
+                choices: fields
+                    .iter()
+                    .filter(|(name, _)| *name == "var")
+                    .map(|(_, value)| value.join(" "))
+                    .collect(),
+            },
+            "button" => OptionKind::Button,
+            "string" => OptionKind::String {
+                default: text("default"),
+            },
+            _ => return None,
+        };
+        Some(EngineOption {
+            name: field("name").filter(|name| !name.is_empty())?,
+            kind,
+        })
+    }
+}
+
+/// something an engine said that chessbored listens for
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EngineMessage {
+    Name(String),
+    Author(String),
+    UciOk,
+    ReadyOk,
+    Option(EngineOption),
+    Info(Info),
+    // nothing when there's no move to play
+    BestMove(Option<Move>),
+}
+
+impl EngineMessage {
+    /// the message in a line of engine output, if it's one that's understood
+    pub fn parse(line: &str) -> Option<EngineMessage> {
+        let words: Vec<&str> = line.split_whitespace().collect();
+        let rest = |from: usize| words.get(from..).unwrap_or_default().join(" ");
+        match words.as_slice() {
+            ["id", "name", ..] => Some(EngineMessage::Name(rest(2))),
+            ["id", "author", ..] => Some(EngineMessage::Author(rest(2))),
+            ["uciok", ..] => Some(EngineMessage::UciOk),
+            ["readyok", ..] => Some(EngineMessage::ReadyOk),
+            ["option", option @ ..] => EngineOption::parse(option).map(EngineMessage::Option),
+            ["info", info @ ..] => Some(EngineMessage::Info(Info::parse(info))),
+            // "(none)" and "0000" both mean there's nothing to play
+            ["bestmove", mv, ..] => Some(EngineMessage::BestMove(mv.parse().ok())),
+            _ => None,
+        }
+    }
+}
+
+/// a UCI engine running as a child process. what it writes is read on a thread of its
+/// own and picked up with `messages`, so nothing ever waits on it
+pub struct Engine {
+    child: Child,
+    stdin: ChildStdin,
+    // a mutex only so the engine can be shared, it's never contended
+    lines: Mutex<Receiver<String>>,
+    // whether the engine's finished saying hello, options and positions wait until it has
+    ready: bool,
+    // options set before it was ready
+    held: Vec<String>,
+    // the position to look at once it's ready
+    waiting: Option<String>,
+    searching: bool,
+    // searches that have been stopped but haven't given their best move yet, anything
+    // they say is left unread
+    stopped: usize,
+    exited: bool,
+    pub name: Option<String>,
+    // what it said can be set
+    pub options: Vec<EngineOption>,
+}
+
+impl Engine {
+    /// start the engine at `path` and ask it to speak UCI
+    pub fn start(path: impl AsRef<Path>) -> io::Result<Engine> {
+        let mut child = Command::new(path.as_ref())
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()?;
+
+        let (stdin, stdout) = match (child.stdin.take(), child.stdout.take()) {
+            (Some(stdin), Some(stdout)) => (stdin, stdout),
+            _ => {
+                let _ = child.kill();
+                return Err(io::Error::other("couldn't talk to the engine"));
+            }
+        };
+
+        let (sender, receiver) = mpsc::channel();
+        thread::spawn(move || {
+            for line in BufReader::new(stdout).lines().map_while(Result::ok) {
+                if sender.send(line).is_err() {
+                    break;
+                }
+            }
+        });
+
+        let mut engine = Engine {
+            child,
+            stdin,
+            lines: Mutex::new(receiver),
+            ready: false,
+            held: vec![],
+            waiting: None,
+            searching: false,
+            stopped: 0,
+            exited: false,
+            name: None,
+            options: vec![],
+        };
+        engine.send("uci")?;
+        Ok(engine)
+    }
+
+    /// write a line to the engine
+    pub fn send(&mut self, command: &str) -> io::Result<()> {
+        writeln!(self.stdin, "{}", command)?;
+        self.stdin.flush()
+    }
+
+    /// everything the engine's said since the last time, leaving out what's left over
+    /// from searches that have been stopped
+    pub fn messages(&mut self) -> io::Result<Vec<EngineMessage>> {
+        let mut lines = vec![];
+        if let Ok(receiver) = self.lines.get_mut() {
+            loop {
+                match receiver.try_recv() {
+                    Ok(line) => lines.push(line),
+                    Err(TryRecvError::Empty) => break,
+                    Err(TryRecvError::Disconnected) => {
+                        self.exited = true;
+                        break;
+                    }
+                }
+            }
+        }
+
+        let mut messages = vec![];
+        for message in lines.iter().filter_map(|line| EngineMessage::parse(line)) {
+            match &message {
+                EngineMessage::Name(name) => self.name = Some(name.clone()),
+                EngineMessage::Option(option) => self.options.push(option.clone()),
+                EngineMessage::UciOk => {
+                    self.ready = true;
+                    for command in std::mem::take(&mut self.held) {
+                        self.send(&command)?;
+                    }
+                    if let Some(fen) = self.waiting.take() {
+                        self.go(&fen)?;
+                    }
+                }
+                EngineMessage::BestMove(_) if self.stopped > 0 => {
+                    self.stopped -= 1;
+                    continue;
+                }
+                EngineMessage::BestMove(_) => self.searching = false,
+                EngineMessage::Info(_) if self.stopped > 0 => continue,
+                _ => {}
+            }
+            messages.push(message);
+        }
+        Ok(messages)
+    }
+
+    /// set one of the options the engine offers. engines only take options while they're
+    /// idle, so a search that's running is stopped and has to be started again
+    pub fn set_option(&mut self, name: &str, value: &str) -> io::Result<()> {
+        self.configure(format!("setoption name {} value {}", name, value))
+    }
+
+    /// press one of the engine's button options. like setting an option, it stops the
+    /// search
+    pub fn press(&mut self, name: &str) -> io::Result<()> {
+        self.configure(format!("setoption name {}", name))
+    }
+
+    fn configure(&mut self, command: String) -> io::Result<()> {
+        if self.ready {
+            self.stop()?;
+            self.send(&command)
+        } else {
+            self.held.push(command);
+            Ok(())
+        }
+    }
+
+    /// look at `position` until told to stop, stopping whatever it was looking at before
+    pub fn analyse(&mut self, position: &Position) -> io::Result<()> {
+        let fen = position.to_fen();
+        if self.ready {
+            self.stop()?;
+            self.go(&fen)
+        } else {
+            self.waiting = Some(fen);
+            Ok(())
+        }
+    }
+
+    fn go(&mut self, fen: &str) -> io::Result<()> {
+        self.send(&format!("position fen {}", fen))?;
+        self.send("go infinite")?;
+        self.searching = true;
+        Ok(())
+    }
+
+    /// stop searching. the best move it gives for the search is left unread
+    pub fn stop(&mut self) -> io::Result<()> {
+        self.waiting = None;
+        if self.searching {
+            self.send("stop")?;
+            self.searching = false;
+            self.stopped += 1;
+        }
+        Ok(())
+    }
+
+    pub fn is_searching(&self) -> bool {
+        self.searching || self.waiting.is_some()
+    }
+
+    /// whether the engine's stopped talking, which it only does once it's gone
+    pub fn has_exited(&self) -> bool {
+        self.exited
+    }
+}
+
+impl Drop for Engine {
+    fn drop(&mut self) {
+        let _ = self.send("quit");
+        let asked = std::time::Instant::now();
+        while asked.elapsed() < QUIT_WAIT {
+            if let Ok(Some(_)) = self.child.try_wait() {
+                return;
+            }
+            thread::sleep(Duration::from_millis(10));
+        }
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::position::STARTING_FEN;
+    use std::time::Instant;
+
+    #[test]
+    fn parse_info() {
+        let line = "info depth 12 seldepth 18 multipv 2 score cp -31 upperbound nodes 48210 \
+                    nps 964200 hashfull 12 time 50 pv e7e5 g1f3 b8c6";
+        let info = match EngineMessage::parse(line) {
+            Some(EngineMessage::Info(info)) => info,
+            other => panic!("{:?}", other),
+        };
+        assert_eq!(info.depth, Some(12));
+        assert_eq!(info.seldepth, Some(18));
+        assert_eq!(info.multipv, Some(2));
+        assert_eq!(info.score, Some(Score::Centipawns(-31)));
+        assert_eq!(info.nodes, Some(48210));
+        assert_eq!(info.nps, Some(964200));
+        assert_eq!(info.time, Some(50));
+        let pv: Vec<String> = info.pv.iter().map(|mv| mv.to_string()).collect();
+        assert_eq!(pv, ["e7e5", "g1f3", "b8c6"]);
+
+        assert_eq!(
+            EngineMessage::parse("info depth 30 score mate -4 pv e1e2"),
+            Some(EngineMessage::Info(Info {
+                depth: Some(30),
+                score: Some(Score::Mate(-4)),
+                pv: vec!["e1e2".parse().unwrap()],
+                ..Info::default()
+            }))
+        );
+        assert_eq!(
+            EngineMessage::parse("bestmove (none)"),
+            Some(EngineMessage::BestMove(None))
+        );
+        assert_eq!(
+            EngineMessage::parse("id name Stockfish 15"),
+            Some(EngineMessage::Name("Stockfish 15".to_string()))
+        );
+        assert_eq!(
+            EngineMessage::parse("Stockfish 15 by the Stockfish developers"),
+            None
+        );
+    }
+
+    #[test]
+    fn parse_options() {
+        let option = |line: &str| match EngineMessage::parse(line) {
+            Some(EngineMessage::Option(option)) => option,
+            other => panic!("{:?}", other),
+        };
+
+        assert_eq!(
+            option("option name Skill Level type spin default 20 min 0 max 20"),
+            EngineOption {
+                name: "Skill Level".to_string(),
+                kind: OptionKind::Spin {
+                    default: 20,
+                    min: 0,
+                    max: 20
+                },
+            }
+        );
+        assert_eq!(
+            option("option name Ponder type check default false").kind,
+            OptionKind::Check { default: false }
+        );
+        assert_eq!(
+            option("option name SyzygyPath type string default <empty>").kind,
+            OptionKind::String {
+                default: String::new()
+            }
+        );
+        assert_eq!(
+            option("option name Clear Hash type button"),
+            EngineOption {
+                name: "Clear Hash".to_string(),
+                kind: OptionKind::Button,
+            }
+        );
+        assert_eq!(
+            option("option name Style type combo default Normal var Solid var Normal var Risky")
+                .kind,
+            OptionKind::Combo {
+                default: "Normal".to_string(),
+                choices: vec![
+                    "Solid".to_string(),
+                    "Normal".to_string(),
+                    "Risky".to_string()
+                ],
+            }
+        );
+        assert_eq!(EngineMessage::parse("option name Threads type dial"), None);
+        assert_eq!(
+            EngineMessage::parse("option type spin default 1 min 1 max 2"),
+            None
+        );
+    }
+
+    // what `engine` says until `done` is happy with it, or it's taken too long
+    fn messages_until(
+        engine: &mut Engine,
+        done: impl Fn(&[EngineMessage]) -> bool,
+    ) -> Vec<EngineMessage> {
+        let started = Instant::now();
+        let mut messages = vec![];
+        while !done(&messages) {
+            assert!(
+                started.elapsed() < Duration::from_secs(5),
+                "gave up waiting, got {:?}",
+                messages
+            );
+            messages.extend(engine.messages().unwrap());
+            thread::sleep(Duration::from_millis(5));
+        }
+        messages
+    }
+
+    fn deepest(messages: &[EngineMessage]) -> Option<&Info> {
+        messages.iter().rev().find_map(|message| match message {
+            EngineMessage::Info(info) if info.depth == Some(2) => Some(info),
+            _ => None,
+        })
+    }
+
+    // a shell script that answers like an engine would, see tests/stand_in_engine.sh
+    #[cfg(unix)]
+    #[test]
+    fn analyse_with_a_stand_in_engine() {
+        let script = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/stand_in_engine.sh");
+        let mut engine = Engine::start(script).unwrap();
+
+        // asked before it's ready, so it waits for uciok
+        let start = Position::from_fen(STARTING_FEN).unwrap();
+        engine.set_option("MultiPV", "2").unwrap();
+        engine.analyse(&start).unwrap();
+        let messages = messages_until(&mut engine, |messages| deepest(messages).is_some());
+        assert!(messages.contains(&EngineMessage::UciOk));
+        assert_eq!(engine.name.as_deref(), Some("stand-in"));
+        assert_eq!(
+            engine.options,
+            [EngineOption {
+                name: "Hash".to_string(),
+                kind: OptionKind::Spin {
+                    default: 16,
+                    min: 1,
+                    max: 1024
+                },
+            }]
+        );
+
+        // it echoes the option and the position it was sent, in that order
+        let echoed: Vec<&str> = messages
+            .iter()
+            .filter_map(|message| match message {
+                EngineMessage::Info(Info {
+                    string: Some(s), ..
+                }) => Some(s.as_str()),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(
+            echoed,
+            [
+                "setoption name MultiPV value 2".to_string(),
+                format!("position fen {}", STARTING_FEN)
+            ]
+        );
+        let info = deepest(&messages).unwrap();
+        assert_eq!(info.score, Some(Score::Centipawns(25)));
+        assert_eq!(start.san_line(&info.pv), ["e4", "e5"]);
+        assert!(engine.is_searching());
+
+        // nothing from the first search gets through once there's a second one
+        let after =
+            Position::from_fen("rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq - 0 1")
+                .unwrap();
+        engine.analyse(&after).unwrap();
+        let messages = messages_until(&mut engine, |messages| deepest(messages).is_some());
+        assert!(!messages
+            .iter()
+            .any(|message| matches!(message, EngineMessage::BestMove(_))));
+        let info = deepest(&messages).unwrap();
+        assert_eq!(after.san_line(&info.pv), ["e5", "Nf3"]);
+
+        // and stopping gives the best move, which is left unread
+        engine.stop().unwrap();
+        assert!(!engine.is_searching());
+        thread::sleep(Duration::from_millis(50));
+        assert_eq!(engine.messages().unwrap(), vec![]);
+        assert!(!engine.has_exited());
+
+        drop(engine);
+    }
+
+    #[test]
+    fn missing_engine() {
+        assert!(Engine::start("/nowhere/an-engine-that-isnt-there").is_err());
+    }
+}
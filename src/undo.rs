@@ -0,0 +1,75 @@
+use crate::{
+    fen::{command_pressed, LoadFen},
+    pieces::Piece,
+    types::WithSelectedPiece,
+};
+use bevy::prelude::*;
+use chessbored::position::Position;
+
+// how many boards back undo can go
+const UNDO_LIMIT: usize = 200;
+
+/// boards to go back to and forward again, as FEN
+#[derive(Component, Default)]
+pub struct UndoHistory {
+    pub undo: Vec<String>,
+    pub redo: Vec<String>,
+    // the board as it was last recorded
+    pub last: String,
+}
+
+/// remember the board as it was before every edit, whatever made it: a piece placed,
+/// taken off or moved, the board cleared, a position loaded or restored
+pub fn record_edits(
+    position: Res<Position>,
+    mut undo_history: ResMut<UndoHistory>,
+    selected_query: Query<&Piece, WithSelectedPiece>,
+) {
+    if !selected_query.is_empty() {
+        // the edit isn't done until the piece in hand is put down
+        return;
+    }
+
+    let fen = position.to_fen();
+    if fen == undo_history.last {
+        return;
+    }
+
+    let before = std::mem::replace(&mut undo_history.last, fen);
+    // the board the app opens with isn't an edit
+    if !before.is_empty() {
+        undo_history.undo.push(before);
+        if undo_history.undo.len() > UNDO_LIMIT {
+            undo_history.undo.remove(0);
+        }
+    }
+    undo_history.redo.clear();
+}
+
+/// cmd / ctrl + z to undo, and cmd / ctrl + shift + z or cmd / ctrl + y to redo
+pub fn undo_redo(
+    keys: Res<Input<KeyCode>>,
+    mut undo_history: ResMut<UndoHistory>,
+    mut load_events: EventWriter<LoadFen>,
+    selected_query: Query<&Piece, WithSelectedPiece>,
+) {
+    if !command_pressed(&keys) || !selected_query.is_empty() {
+        return;
+    }
+    let shift = keys.pressed(KeyCode::LShift) || keys.pressed(KeyCode::RShift);
+
+    let undo_history = &mut *undo_history;
+    let (from, to) = if keys.just_pressed(KeyCode::Z) && !shift {
+        (&mut undo_history.undo, &mut undo_history.redo)
+    } else if keys.just_pressed(KeyCode::Z) || keys.just_pressed(KeyCode::Y) {
+        (&mut undo_history.redo, &mut undo_history.undo)
+    } else {
+        return;
+    };
+
+    if let Some(fen) = from.pop() {
+        // the board's already recorded as this once it's loaded
+        to.push(std::mem::replace(&mut undo_history.last, fen.clone()));
+        load_events.send(LoadFen(fen));
+    }
+}
@@ -0,0 +1,274 @@
+use crate::{
+    board::{BoardLayout, Rank, Square},
+    pieces::{Kind, Side},
+};
+use bevy::prelude::*;
+use chessbored::position::{Position, Turn};
+use std::fmt;
+
+/// something about the position on the board that an engine won't accept
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Problem {
+    MissingKing(Turn),
+    ExtraKings(Turn, Vec<Square>),
+    PawnOnBackRank(Square),
+    TooManyPieces(Turn, usize),
+    ImpossiblePromotions(Turn),
+    // the side that isn't moving is in check, with the king and who's checking it
+    NotToMoveInCheck(Turn, Vec<Square>),
+}
+
+impl Problem {
+    /// squares to mark on the board for this problem
+    pub fn squares(&self) -> Vec<Square> {
+        match self {
+            Self::ExtraKings(_, squares) | Self::NotToMoveInCheck(_, squares) => squares.clone(),
+            Self::PawnOnBackRank(square) => vec![*square],
+            Self::MissingKing(_) | Self::TooManyPieces(_, _) | Self::ImpossiblePromotions(_) => {
+                vec![]
+            }
+        }
+    }
+}
+
+fn side_name(turn: &Turn) -> &str {
+    match turn {
+        Turn::White => "white",
+        Turn::Black => "black",
+    }
+}
+
+impl fmt::Display for Problem {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::MissingKing(turn) => write!(fmt, "{} has no king", side_name(turn)),
+            Self::ExtraKings(turn, squares) => {
+                write!(fmt, "{} has {} kings", side_name(turn), squares.len())
+            }
+            Self::PawnOnBackRank(square) => write!(fmt, "pawn on a back rank at {}", square),
+            Self::TooManyPieces(turn, count) => write!(
+                fmt,
+                "{} has {} pieces, no more than 16 fit in a game",
+                side_name(turn),
+                count
+            ),
+            Self::ImpossiblePromotions(turn) => write!(
+                fmt,
+                "{} has more promoted pieces than missing pawns",
+                side_name(turn)
+            ),
+            Self::NotToMoveInCheck(turn, _) => write!(
+                fmt,
+                "{} is in check but it's {}'s move",
+                side_name(turn),
+                side_name(&turn.other())
+            ),
+        }
+    }
+}
+
+#[derive(Component, Default)]
+pub struct PositionProblems {
+    pub problems: Vec<Problem>,
+}
+
+#[derive(Component)]
+pub struct ProblemMarker;
+
+pub fn validate_position(position: Res<Position>, mut position_problems: ResMut<PositionProblems>) {
+    if !position.is_changed() {
+        return;
+    }
+
+    let problems = problems_for_position(&position);
+
+    // only touch the resource when something's different so markers aren't respawned every frame
+    if problems != position_problems.problems {
+        position_problems.problems = problems;
+    }
+}
+
+pub fn mark_problem_squares(
+    mut commands: Commands,
+    layout: Res<BoardLayout>,
+    position_problems: Res<PositionProblems>,
+    marker_query: Query<Entity, With<ProblemMarker>>,
+) {
+    // the markers move with the squares when the board's flipped or resized
+    if !position_problems.is_changed() && !layout.is_changed() {
+        return;
+    }
+
+    for entity in marker_query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+
+    for square in position_problems.problems.iter().flat_map(|p| p.squares()) {
+        let pos = layout.square_center(&square);
+        commands
+            .spawn_bundle(SpriteBundle {
+                sprite: Sprite {
+                    color: Color::rgba(0.8, 0.1, 0.1, 0.35),
+                    custom_size: Some(Vec2::splat(layout.square_size)),
+                    ..default()
+                },
+                // above the squares, below the pieces
+                transform: Transform::from_xyz(pos.x, pos.y, -0.005),
+                ..default()
+            })
+            .insert(ProblemMarker);
+    }
+}
+
+pub fn problems_for_position(position: &Position) -> Vec<Problem> {
+    let mut problems = vec![];
+
+    for side in [Turn::White, Turn::Black] {
+        let pieces: Vec<(&Kind, Square)> = position
+            .pieces()
+            .filter(|(_, s)| s.turn() == side)
+            .map(|(square, s)| (s.kind(), square))
+            .collect();
+        let count = |kind: Kind| pieces.iter().filter(|(k, _)| **k == kind).count();
+
+        let kings: Vec<Square> = pieces
+            .iter()
+            .filter(|(k, _)| **k == Kind::King)
+            .map(|(_, square)| *square)
+            .collect();
+        if kings.is_empty() {
+            problems.push(Problem::MissingKing(side));
+        } else if kings.len() > 1 {
+            problems.push(Problem::ExtraKings(side, kings));
+        }
+
+        if pieces.len() > 16 {
+            problems.push(Problem::TooManyPieces(side, pieces.len()));
+        }
+
+        // every piece past the starting set had to come from a pawn that's no longer a pawn
+        let promoted = count(Kind::Queen).saturating_sub(1)
+            + count(Kind::Rook).saturating_sub(2)
+            + count(Kind::Bishop).saturating_sub(2)
+            + count(Kind::Knight).saturating_sub(2);
+        if count(Kind::Pawn) + promoted > 8 {
+            problems.push(Problem::ImpossiblePromotions(side));
+        }
+    }
+
+    for (square, side) in position.pieces() {
+        if let Side::White(Kind::Pawn) | Side::Black(Kind::Pawn) = side {
+            if square.rank == Rank::One || square.rank == Rank::Eight {
+                problems.push(Problem::PawnOnBackRank(square));
+            }
+        }
+    }
+
+    let not_to_move = position.turn.other();
+    let king = match not_to_move {
+        Turn::White => Side::White(Kind::King),
+        Turn::Black => Side::Black(Kind::King),
+    };
+    for (king_square, _) in position.pieces().filter(|(_, s)| **s == king) {
+        let checkers = position.attackers(&king_square, position.turn);
+        if !checkers.is_empty() {
+            let mut squares = vec![king_square];
+            squares.extend(checkers);
+            problems.push(Problem::NotToMoveInCheck(not_to_move, squares));
+        }
+    }
+
+    problems
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn problems(fen: &str) -> Vec<Problem> {
+        problems_for_position(&Position::from_fen(fen).unwrap())
+    }
+
+    fn square(name: &str) -> Square {
+        name.parse().unwrap()
+    }
+
+    fn squares(names: &[&str]) -> Vec<Square> {
+        names.iter().map(|name| square(name)).collect()
+    }
+
+    #[test]
+    fn starting_position_is_clean() {
+        assert_eq!(problems(chessbored::position::STARTING_FEN), vec![]);
+    }
+
+    #[test]
+    fn missing_king() {
+        assert_eq!(
+            problems("8/8/8/8/8/8/8/4K3 w - - 0 1"),
+            vec![Problem::MissingKing(Turn::Black)]
+        );
+    }
+
+    #[test]
+    fn extra_kings() {
+        assert_eq!(
+            problems("4k3/8/8/8/8/8/8/2K1K3 w - - 0 1"),
+            vec![Problem::ExtraKings(Turn::White, squares(&["c1", "e1"]))]
+        );
+    }
+
+    #[test]
+    fn pawns_on_back_ranks() {
+        assert_eq!(
+            problems("p3k3/8/8/8/8/8/8/4K2P w - - 0 1"),
+            vec![
+                Problem::PawnOnBackRank(square("h1")),
+                Problem::PawnOnBackRank(square("a8")),
+            ]
+        );
+    }
+
+    #[test]
+    fn too_many_pieces() {
+        // a second king is one piece too many without being a promotion
+        assert_eq!(
+            problems("4k3/8/8/8/8/4K3/PPPPPPPP/RNBQKBNR w - - 0 1"),
+            vec![
+                Problem::ExtraKings(Turn::White, squares(&["e1", "e3"])),
+                Problem::TooManyPieces(Turn::White, 17),
+            ]
+        );
+    }
+
+    #[test]
+    fn impossible_promotions() {
+        // a second queen with all eight pawns still on the board
+        assert_eq!(
+            problems("4k3/8/8/8/8/8/PPPPPPPP/QQ2K3 w - - 0 1"),
+            vec![Problem::ImpossiblePromotions(Turn::White)]
+        );
+    }
+
+    #[test]
+    fn side_not_to_move_in_check() {
+        assert_eq!(
+            problems("4k3/8/8/8/8/8/8/4R1K1 w - - 0 1"),
+            vec![Problem::NotToMoveInCheck(
+                Turn::Black,
+                squares(&["e8", "e1"])
+            )]
+        );
+    }
+
+    #[test]
+    fn impossible_castling_and_en_passant_are_dropped() {
+        // rights the pieces can't back up never reach an engine, so they aren't problems
+        let fen = "4k3/8/8/8/8/8/8/4K3 w KQkq e6 0 1";
+        assert_eq!(problems(fen), vec![]);
+        assert_eq!(
+            Position::from_fen(fen).unwrap().to_fen(),
+            "4k3/8/8/8/8/8/8/4K3 w - - 0 1"
+        );
+    }
+}
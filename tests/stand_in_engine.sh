@@ -0,0 +1,45 @@
+#!/bin/sh
+# a stand-in UCI engine for the tests. it echoes the options and the position it's
+# sent, and always finds the same two moves for white or black until it's told to stop
+
+side=w
+while read -r line; do
+    case "$line" in
+        uci)
+            echo "id name stand-in"
+            echo "id author chessbored"
+            echo "option name Hash type spin default 16 min 1 max 1024"
+            echo "uciok"
+            ;;
+        isready)
+            echo "readyok"
+            ;;
+        "setoption "*)
+            echo "info string $line"
+            ;;
+        "position "*)
+            case "$line" in
+                *" b "*) side=b ;;
+                *) side=w ;;
+            esac
+            echo "info string $line"
+            ;;
+        go*)
+            if [ "$side" = w ]; then
+                echo "info depth 1 seldepth 1 multipv 1 score cp 31 nodes 20 nps 2000 time 10 pv e2e4"
+                echo "info depth 2 seldepth 2 multipv 1 score cp 25 nodes 420 nps 21000 time 20 pv e2e4 e7e5"
+                best=e2e4
+            else
+                echo "info depth 1 seldepth 1 multipv 1 score cp -20 nodes 20 nps 2000 time 10 pv e7e5"
+                echo "info depth 2 seldepth 2 multipv 1 score cp -28 nodes 440 nps 22000 time 20 pv e7e5 g1f3"
+                best=e7e5
+            fi
+            ;;
+        stop)
+            echo "bestmove $best"
+            ;;
+        quit)
+            exit 0
+            ;;
+    esac
+done